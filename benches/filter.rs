@@ -0,0 +1,104 @@
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, Criterion,
+};
+use pancurses::Input;
+use rayon::prelude::*;
+use stacklet::external::model::parse_stdout;
+use stacklet::interface::backend::Headless;
+use stacklet::interface::window::{init, Options};
+use stacklet::utils::filter::{
+    fuzzy_match, fuzzy_score_candidate, Candidate, Query, TermKind,
+};
+
+/// Generate a list of file paths to filter.
+fn paths(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|i| {
+            format!(
+                "src/module_{}/subModule{}/file_{}.rs",
+                i % 97,
+                (i * 7919) % 1013,
+                i
+            )
+        })
+        .collect()
+}
+
+fn fuzzy(c: &mut Criterion) {
+    let candidates: Vec<Candidate> = paths(100_000)
+        .iter()
+        .map(|path| Candidate::new(path))
+        .collect();
+
+    c.bench_function("fuzzy_score 100k", |b| {
+        b.iter(|| {
+            candidates
+                .iter()
+                .filter_map(|candidate| {
+                    fuzzy_score_candidate(
+                        candidate,
+                        black_box("mod12file"),
+                        false,
+                    )
+                })
+                .count()
+        })
+    });
+
+    let query = Query::parse("mod12 file !_3 rs$", TermKind::Fuzzy);
+    c.bench_function("extended query 100k parallel", |b| {
+        b.iter(|| {
+            candidates
+                .par_iter()
+                .filter_map(|candidate| query.find_candidate(candidate, false))
+                .count()
+        })
+    });
+}
+
+fn levenshtein(c: &mut Criterion) {
+    let paths = paths(10_000);
+
+    c.bench_function("fuzzy_match 10k", |b| {
+        b.iter(|| {
+            paths
+                .iter()
+                .filter(|path| {
+                    fuzzy_match(black_box("src/module_1/file"), path)
+                })
+                .count()
+        })
+    });
+}
+
+fn typing(c: &mut Criterion) {
+    let stdout = format!(
+        "INPUT(filter=\"fuzzy\")\n{}",
+        paths(100_000)
+            .iter()
+            .map(|path| format!("TEXT(\"{}\")", path))
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+    let model = parse_stdout(&stdout).unwrap().widgets;
+
+    c.bench_function("type 6 characters into 100k rows", |b| {
+        b.iter_batched(
+            || {
+                let keys = "file12".chars().map(Input::Character);
+                (Headless::new(40, 120, keys), model.clone())
+            },
+            |(mut backend, mut model)| {
+                init(&mut backend, &mut model, &Options::default())
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = fuzzy, levenshtein, typing
+}
+criterion_main!(benches);
@@ -1,48 +1,364 @@
-use crate::external::model::parse_stdout;
-use crate::Widget;
-use std::process::{exit, Command};
+use crate::external::model::{parse_stdout_as, Protocol, Screen};
+use crate::utils::error::RunError;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{exit, Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::str::FromStr;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 use fork::{daemon, Fork};
 
+/// Encoding of INPUT_CONTENT, (the content of every input widget), CHECKED, (the names of checked
+/// checkboxes), and SELECTED, (the selected option of every select widget).
+#[derive(Debug, PartialEq, Clone)]
+pub enum Encoding {
+    Plain,
+    Json,
+    Url,
+}
+
+impl FromStr for Encoding {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            | "plain" => Ok(Encoding::Plain),
+            | "json" => Ok(Encoding::Json),
+            | "url" => Ok(Encoding::Url),
+            | _ => Err(()),
+        }
+    }
+}
+
+/// Encode the `(name, content)` of every input widget as a single string.
+///
+/// - **Plain**: contents deliminated by `:`, (ambiguous if a content contains `:`)
+/// - **Json**: object of contents keyed by name, (or by position if unnamed)
+/// - **Url**: percent-encoded `key=content` pairs joined by `&`, keyed as in json
+pub fn encode_inputs(
+    inputs: &[(String, String)],
+    encoding: &Encoding,
+) -> String {
+    // unnamed inputs are keyed by their position among all inputs
+    let keyed = inputs
+        .iter()
+        .enumerate()
+        .map(|(position, (name, content))| {
+            if name.is_empty() {
+                (position.to_string(), content)
+            } else {
+                (name.clone(), content)
+            }
+        });
+
+    match encoding {
+        | Encoding::Plain => inputs
+            .iter()
+            .map(|(_, content)| content.as_str())
+            .collect::<Vec<_>>()
+            .join(":"),
+        | Encoding::Json => serde_json::Value::Object(
+            keyed
+                .map(|(key, content)| (key, content.as_str().into()))
+                .collect(),
+        )
+        .to_string(),
+        | Encoding::Url => keyed
+            .map(|(key, content)| {
+                format!("{}={}", percent_encode(&key), percent_encode(content))
+            })
+            .collect::<Vec<_>>()
+            .join("&"),
+    }
+}
+
+/// Encode the names of the checked checkboxes as a single string.
+///
+/// - **Plain**: names deliminated by `,`, (ambiguous if a name contains `,`)
+/// - **Json**: array of names
+/// - **Url**: percent-encoded names joined by `,`
+pub fn encode_checked(names: &[String], encoding: &Encoding) -> String {
+    match encoding {
+        | Encoding::Plain => names.join(","),
+        | Encoding::Json => serde_json::Value::Array(
+            names.iter().map(|name| name.as_str().into()).collect(),
+        )
+        .to_string(),
+        | Encoding::Url => names
+            .iter()
+            .map(|name| percent_encode(name))
+            .collect::<Vec<_>>()
+            .join(","),
+    }
+}
+
+/// Encode the `(name, option)` of every select widget as a single string.
+///
+/// Encoded as `encode_inputs` does, except for **Plain**, which writes a `name=option` line per
+/// select, (ambiguous if a name contains `=` or an option a newline).
+pub fn encode_selected(
+    selected: &[(String, String)],
+    encoding: &Encoding,
+) -> String {
+    match encoding {
+        | Encoding::Plain => selected
+            .iter()
+            .map(|(name, option)| format!("{}={}", name, option))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        | encoding => encode_inputs(selected, encoding),
+    }
+}
+
+/// Percent-encode every byte of s outside of the unreserved characters of RFC 3986.
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|byte| match byte {
+            | b'A'..=b'Z'
+            | b'a'..=b'z'
+            | b'0'..=b'9'
+            | b'-'
+            | b'.'
+            | b'_'
+            | b'~' => (byte as char).to_string(),
+            | _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Runtime variables passed to the executable on every run.
+///
+/// `inputs` holds the `(name, content)` of every input widget, each named one is also passed as its own
+/// STACKLET_INPUT_<name> variable.
+#[derive(Debug, Default, Clone)]
+pub struct Runtime {
+    pub input: String,
+    pub input_content: String,
+    pub inputs: Vec<(String, String)>,
+    pub selection: String,
+    pub selection_key: String,
+    pub selections: String,
+    pub checked: String,
+    pub selected: String,
+    pub data: String,
+}
+
+impl Runtime {
+    /// Return the runtime variables as `(name, value)` pairs.
+    pub fn vars(&self) -> Vec<(String, String)> {
+        let mut vars: Vec<(String, String)> = [
+            ("INPUT", &self.input),
+            ("INPUT_CONTENT", &self.input_content),
+            ("SELECTION", &self.selection),
+            ("SELECTION_KEY", &self.selection_key),
+            ("SELECTIONS", &self.selections),
+            ("CHECKED", &self.checked),
+            ("SELECTED", &self.selected),
+            ("DATA", &self.data),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.clone()))
+        .collect();
+
+        // named inputs, (characters not allowed in a variable name are replaced)
+        for (name, content) in &self.inputs {
+            if !name.is_empty() {
+                let name: String = name
+                    .chars()
+                    .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                    .collect();
+                vars.push((
+                    format!("STACKLET_INPUT_{}", name),
+                    content.clone(),
+                ));
+            }
+        }
+
+        vars
+    }
+
+    /// Encode the runtime variables as a single line JSON object, (an event for a coprocess).
+    pub fn to_event(&self) -> String {
+        let event: serde_json::Map<String, serde_json::Value> = self
+            .vars()
+            .into_iter()
+            .map(|(name, value)| (name, value.into()))
+            .collect();
+
+        serde_json::Value::Object(event).to_string()
+    }
+}
+
 /// Run provided executable with the provided environment and parse the stdout.
 ///
 /// Takes a path for the executable and runs via `sh` with the provided runtime variables.
-/// Takes the stdout of the executable and passes to `external::model::parse_stdout` to generate the model
-/// and new runtime variables.
+/// Takes the stdout of the executable and passes to `external::model::parse_stdout_as` to generate the screen,
+/// (ui model and new runtime variables), reading it with the given protocol unless the stdout picks its own.
+/// Returns the parse errors if the stdout is not a valid model, or the stderr if the executable
+/// fails, (left to the caller to report once the terminal is restored).
 pub fn run_executable(
     path: &str,
-    input: &str,
-    input_content: &str,
-    selection: &str,
-    data: &str,
-) -> (Vec<Widget>, String) {
+    protocol: &Protocol,
+    runtime: &Runtime,
+) -> Result<Screen, RunError> {
     let output = Command::new("sh")
         .arg("-c")
-        .arg(&path)
-        .env("INPUT", &input)
-        .env("INPUT_CONTENT", &input_content)
-        .env("SELECTION", &selection)
-        .env("DATA", &data)
+        .arg(path)
+        .envs(runtime.vars())
         .output()
-        .expect("Failed to run provided executable");
+        .map_err(|error| {
+            RunError::Failed(format!(
+                "Failed to run provided executable: {}",
+                error
+            ))
+        })?;
 
     if output.status.success() {
         let result = String::from_utf8_lossy(&output.stdout);
-        return parse_stdout(&result);
+        Ok(parse_stdout_as(&result, protocol.clone())?)
     } else {
         let error = String::from_utf8_lossy(&output.stderr);
-        eprintln!("{}", error);
-        exit(1);
+        Err(RunError::Failed(error.to_string()))
+    }
+}
+
+/// Executable kept running for the lifetime of the program, (coprocess mode).
+///
+/// Rather than running the executable once per interaction, it is spawned once and sent events on
+/// its stdin, one JSON object of runtime variables per line, e.g.
+///   {"INPUT":"","INPUT_CONTENT":"foo","SELECTION":"bar","DATA":""}
+/// The executable answers each event with a model on stdout, terminated by a line containing only
+/// the frame delimiter, (or by exiting). Its stderr is read as it is written, (so it never blocks
+/// writing to a full pipe), and reported if it fails.
+pub struct Coprocess {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+    stderr: Option<JoinHandle<String>>,
+    delimiter: String,
+}
+
+impl Coprocess {
+    /// Spawn the executable via `sh` with piped stdin, stdout and stderr.
+    ///
+    /// Fails like `run_executable` if it cannot be run.
+    pub fn spawn(path: &str, delimiter: &str) -> Result<Coprocess, RunError> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|error| {
+                RunError::Failed(format!(
+                    "Failed to run provided executable: {}",
+                    error
+                ))
+            })?;
+
+        let stdin = child.stdin.take();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+
+        // collect stderr until the executable closes it
+        let stderr = child.stderr.take().map(|mut stderr| {
+            thread::spawn(move || {
+                let mut error = Vec::new();
+                let _ = stderr.read_to_end(&mut error);
+                String::from_utf8_lossy(&error).to_string()
+            })
+        });
+
+        Ok(Coprocess {
+            child,
+            stdin,
+            stdout,
+            stderr,
+            delimiter: delimiter.to_string(),
+        })
+    }
+
+    /// Send the runtime variables to the executable as an event and parse the frame it answers with.
+    ///
+    /// A frame cut short by the executable exiting is parsed as is, so an executable that exits
+    /// without answering ends the program like an empty stdout does. Fails like `run_executable`
+    /// if it exits unsuccessfully.
+    pub fn request(
+        &mut self,
+        protocol: &Protocol,
+        runtime: &Runtime,
+    ) -> Result<Screen, RunError> {
+        if let Some(stdin) = self.stdin.as_mut() {
+            // a closed stdin means the executable has exited, which is handled on read
+            let _ = writeln!(stdin, "{}", runtime.to_event())
+                .and_then(|_| stdin.flush());
+        }
+
+        let mut frame = String::new();
+        let mut line = String::new();
+
+        loop {
+            line.clear();
+            let read = self.stdout.read_line(&mut line).map_err(|error| {
+                RunError::Failed(format!(
+                    "Failed to read from provided executable: {}",
+                    error
+                ))
+            })?;
+
+            if read == 0 {
+                // executable closed its stdout, report failure like a single run would
+                self.stdin = None;
+                let status = self.child.wait();
+                if !matches!(status, Ok(status) if status.success()) {
+                    let error = self
+                        .stderr
+                        .take()
+                        .and_then(|stderr| stderr.join().ok())
+                        .unwrap_or_default();
+                    return Err(RunError::Failed(error));
+                }
+                break;
+            }
+
+            if line.trim_end_matches(['\n', '\r']) == self.delimiter {
+                break;
+            }
+            frame.push_str(&line);
+        }
+
+        Ok(parse_stdout_as(&frame, protocol.clone())?)
+    }
+}
+
+impl Drop for Coprocess {
+    /// Close the executable's stdin, (its signal to exit), and wait for it, killing it if it is
+    /// still running after a second.
+    fn drop(&mut self) {
+        self.stdin = None;
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while let Ok(None) = self.child.try_wait() {
+            if Instant::now() >= deadline {
+                let _ = self.child.kill();
+                let _ = self.child.wait();
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
     }
 }
 
 /// Fork a child process and run the given command on it.
+///
+/// The calling process exits once the child is forked, (without running destructors), so the
+/// terminal must be restored first by dropping its backend, (see `interface::backend::Inline`).
 pub fn spawn_detached_child(command: &str) {
     if let Ok(Fork::Child) = daemon(false, false) {
         Command::new("sh")
             .arg("-c")
-            .arg(&command)
-            .spawn()
+            .arg(command)
+            .status()
             .expect("Failed to launch subprocess");
+        exit(0);
     }
 }
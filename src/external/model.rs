@@ -1,115 +1,590 @@
-use crate::external::exec;
-use crate::external::widget::{Filter, Widget};
-use regex::Regex;
+use crate::external::syntax::parse_macro;
+use crate::external::widget::{Case, Filter, Widget};
+use crate::interface::theme::Style;
+use crate::utils::error::{ParseError, ParseErrorKind};
+use serde_json::Value;
+use std::str::FromStr;
 
-/// Generate the model, (vector of ui widgets), and runtime data from the stdout of executable.
+/// Wire format an executable uses to describe its ui model on stdout.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Protocol {
+    Macro,
+    Json,
+}
+
+impl FromStr for Protocol {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            | "macro" => Ok(Protocol::Macro),
+            | "json" => Ok(Protocol::Json),
+            | _ => Err(()),
+        }
+    }
+}
+
+/// Everything read from a single run of the executable.
+///
+/// `widgets` is the ui model, `data` the content of the DATA() widget and `multi_select` is set
+/// by the MULTISELECT() directive. `quit_command` is the command given to QUIT(), left to the
+/// caller to run, (see `external::exec::spawn_detached_child`).
+#[derive(Debug, Default)]
+pub struct Screen {
+    pub widgets: Vec<Widget>,
+    pub data: String,
+    pub multi_select: bool,
+    pub quit_command: Option<String>,
+}
+
+/// A single line of stdout, decoded from either protocol.
+///
+/// `name` is the lowercase widget name and `content` its positional argument, (the `content` or
+/// `command` key in json).
+struct Element {
+    name: String,
+    content: Option<String>,
+    params: Vec<Param>,
+    column: usize,
+}
+
+/// A named parameter of an element and the column it was written at.
 ///
-/// ## UI Model creation
+/// `items` holds the values of a json array as written, (`value` joins them with `|`), and is
+/// `None` for a single value.
+struct Param {
+    name: String,
+    value: String,
+    items: Option<Vec<String>>,
+    column: usize,
+}
+
+/// Generate the screen, (ui model and runtime data), from the stdout of executable.
+///
+/// Stdout is read with the macro protocol unless its first line is a protocol header,
+/// see `parse_stdout_as`.
+pub fn parse_stdout(stdout: &str) -> Result<Screen, Vec<ParseError>> {
+    parse_stdout_as(stdout, Protocol::Macro)
+}
+
+/// Generate the screen from the stdout of executable using the given protocol.
+///
+/// ## Protocol selection
+///
+/// The protocol defaults to the one given, (set from the commandline), but can be picked by the
+/// executable itself with a header on the first line of stdout, `PROTOCOL("macro")` or `PROTOCOL("json")`.
+/// The header line is not part of the model.
+///
+/// ## UI Model creation (macro protocol)
 ///
 /// The stdout is parsed to generate a ui model according to the following BNF form:
-///   <input> ::= INPUT '(' <param_list> ')'
-///   <param_list> ::= <param> | <param>, <param_list>
+///   <line> ::= <input> | <text> | <checkbox> | <select> | <data> | <multiselect> | <quit>
+///   <input> ::= INPUT '(' [<param_list>] ')'
+///   <param_list> ::= <param> | <param> <separator> <param_list>
+///   <separator> ::= ',' | <whitespace> | <separator> <separator>
 ///   <param> ::= <param_name> '=' <param_value>
 ///   <param_name> ::= [a-zA-Z_][a-zA-Z0-9_]*
-///   <param_value> ::= '"' <string_content> '"'
-///   <text> ::= TEXT '(' '"' <string_content> '"' ')'
-///   <data> ::= DATA '(' '"' <string_content> '"' ')'
-///   <quit> ::= QUIT '(' ')' | QUIT '(' '"' <string_content> '"' ')'
-///   <string_content> ::= [^"]*
+///   <param_value> ::= <string> | [^\s,)"]+
+///   <text> ::= TEXT '(' <string> ')' | TEXT '(' <string> <separator> <param_list> ')'
+///   <checkbox> ::= CHECKBOX '(' [<param_list>] ')'
+///   <select> ::= SELECT '(' <param_list> ')'
+///   <data> ::= DATA '(' <string> ')'
+///   <multiselect> ::= MULTISELECT '(' ')'
+///   <quit> ::= QUIT '(' ')' | QUIT '(' <string> ')'
+///   <string> ::= '"' <string_char>* '"'
+///   <string_char> ::= [^"\\] | '\\' <escape>
+///   <escape> ::= '"' | '\\' | 'n' | 't' | 'r' | 'u' <hex>{4} | 'u{' <hex>{1,6} '}'
+///
+/// Whitespace is allowed around a macro and between its parameters. Quoted strings can be
+/// produced from any string with `external::syntax::escape`.
+///
+/// INPUT takes `name`, (each named input is passed back as STACKLET_INPUT_<name>), `label`, `placeholder`,
+/// (shown while the content is empty), `content`, `filter`, (`off`, `exact`, `fuzzy`, `regex`, `prefix` or
+/// `glob`), `case`, (`smart`, `sensitive` or `insensitive`, the default), `normalize`, (`true` to ignore
+/// diacritics when filtering), and `max_width`, (the widest content can be typed in columns, 32 by
+/// default, longer content scrolls inside a narrower terminal).
+/// TEXT takes the optional parameters `value`, (passed back as SELECTION when chosen, defaults to
+/// the displayed string), and `key`, (a hidden identifier passed back as SELECTION_KEY).
+/// INPUT and TEXT also take the style they are drawn in over the theme, `fg` and `bg`, (a colour name,
+/// palette index or `default`, see `interface::theme::Color`), and `bold`, `dim` and `underline`,
+/// (`true` or `false`).
+/// CHECKBOX takes `label`, `checked`, (`true` or `false`), and `name`, (defaults to the label), the
+/// names of all checked boxes are passed back as CHECKED, (encoded like INPUT_CONTENT, see
+/// `external::exec::encode_checked`).
+/// SELECT takes `label`, `name`, (defaults to the label), `options`, (separated by `|`, or a json
+/// array), and `selected`, (defaults to the first option), the selected option of each is passed back
+/// in SELECTED, (encoded like INPUT_CONTENT, see `external::exec::encode_selected`).
+///
+/// ## UI Model creation (json protocol)
+///
+/// Each line of stdout is a JSON object whose `type` names the widget, the remaining keys mirror
+/// the macro parameters:
+///   {"type": "input", "name": "...", "label": "...", "placeholder": "...", "content": "...", "filter": "...", "case": "...", "normalize": true, "max_width": 32, "fg": "...", "bold": true}
+///   {"type": "text", "content": "...", "value": "...", "key": "...", "bg": "...", "underline": true}
+///   {"type": "checkbox", "label": "...", "name": "...", "checked": true}
+///   {"type": "select", "label": "...", "name": "...", "options": ["...", "..."], "selected": "..."}
+///   {"type": "data", "content": "..."}
+///   {"type": "multiselect"}
+///   {"type": "quit"} | {"type": "quit", "command": "..."}
 ///
 /// ## Runtime data generation
 ///
 /// Data is set by the DATA() widget and returned accordingly.
-/// Since the program quits if no stdout is provided, QUIT() clears the model, (as if there was no stdout), and returns.
-pub fn parse_stdout(stdout: &str) -> (Vec<Widget>, String) {
+/// MULTISELECT() lets the user mark several TEXT widgets on this screen, their values are passed back
+/// as SELECTIONS.
+/// Since the program quits if no stdout is provided, QUIT() clears the model, (as if there was no stdout), and returns
+/// its command, (parsing runs nothing).
+///
+/// ## Errors
+///
+/// Blank lines are skipped, (leaving a gap in the model), any other line that is not a valid widget
+/// produces a `ParseError`. Parsing continues past errors so that all of them are returned together,
+/// (without the QUIT() command). Json errors point at the invalid syntax, the
+/// key of an invalid parameter, or the `type` key of an object missing a parameter, (the start of the
+/// object if it has no type).
+pub fn parse_stdout_as(
+    stdout: &str,
+    protocol: Protocol,
+) -> Result<Screen, Vec<ParseError>> {
     let mut widgets = Vec::new();
+    let mut errors = Vec::new();
     let mut data = String::from("");
+    let mut multi_select = false;
+    let mut quit_command = None;
+    let mut protocol = protocol;
+    let mut lines = stdout.lines().enumerate().peekable();
 
-    // regex for widgets, see BNF form above
-    let input_regex = Regex::new(r"INPUT\s*\((.*?)\)").unwrap();
-    let param_regex =
-        Regex::new(r#"(\w+)\s*=\s*\"?([^\",]+)\"?,?\s*"#).unwrap();
-    let text_regex = Regex::new(r#"TEXT\("(.*)"\)"#).unwrap();
-    let data_regex = Regex::new(r#"DATA\("(.*)"\)"#).unwrap();
-    let quit_regex = Regex::new(r#"QUIT\("([^"]*)"\)"#).unwrap();
+    // a protocol header on the first line overrides the given protocol
+    if let Some(Ok(header)) = lines.peek().map(|(_, line)| parse_macro(line)) {
+        if header.name == "PROTOCOL" {
+            let snippet = lines.peek().unwrap().1.to_string();
+            match header.positional() {
+                | Some(arg) => match arg.value.parse() {
+                    | Ok(header_protocol) => protocol = header_protocol,
+                    | Err(_) => errors.push(ParseError {
+                        line: 1,
+                        column: arg.column,
+                        kind: ParseErrorKind::InvalidValue {
+                            param: "protocol".to_string(),
+                            value: arg.value.clone(),
+                        },
+                        snippet,
+                    }),
+                },
+                | None => errors.push(ParseError {
+                    line: 1,
+                    column: header.column,
+                    kind: ParseErrorKind::MissingArgument(
+                        "protocol".to_string(),
+                    ),
+                    snippet,
+                }),
+            }
+            lines.next();
+        }
+    }
 
     let mut level: i32 = 0;
-    let mut unique_id: usize = 0;
-
-    // match up each line of stdout with widget regex
-    for line in stdout.lines() {
-        if let Some(captures) = input_regex.captures(line) {
-            // found input widget, initialize parameters to default values
-            let params_str = captures.get(1).unwrap().as_str();
-            let mut max_width = 32;
-            let mut filter = Filter::Off;
-            let mut label = String::new();
-            let mut placeholder = String::new();
-            let mut content = String::new();
-
-            for param_match in param_regex.captures_iter(params_str) {
-                let param = param_match.get(1).unwrap().as_str();
-                let value = param_match.get(2).unwrap().as_str();
-
-                // set parameter variable for each valid parameter
-                match param {
-                    | "max_width" => max_width = value.parse().unwrap_or(32),
-                    | "filter" => {
-                        filter = value.parse().unwrap_or_else(|_| {
-                            eprintln!(
-                                "Invalid filter value: {}",
-                                &value.to_string()
-                            );
-                            std::process::exit(1); // TODO: add error utils module
-                        })
+
+    // decode each line of stdout according to the protocol
+    for (index, line) in lines {
+        let element = match protocol {
+            | Protocol::Macro => parse_macro_line(line),
+            | Protocol::Json => parse_json_line(line),
+        };
+
+        // report errors against this line of stdout
+        let error = |column: usize, kind: ParseErrorKind| ParseError {
+            line: index + 1,
+            column,
+            kind,
+            snippet: line.to_string(),
+        };
+
+        // directives take no row in the model, every other line does, (even blank ones)
+        let mut takes_row = true;
+
+        match element {
+            | Ok(Some(element)) if element.name == "input" => {
+                // found input widget, initialize parameters to default values
+                let mut max_width = 32;
+                let mut filter = Filter::Off;
+                let mut case = Case::Insensitive;
+                let mut normalize = false;
+                let mut name = String::new();
+                let mut label = String::new();
+                let mut placeholder = String::new();
+                let mut content = String::new();
+                let mut style = Style::default();
+
+                for param in element.params {
+                    let invalid_value = ParseErrorKind::InvalidValue {
+                        param: param.name.clone(),
+                        value: param.value.clone(),
+                    };
+
+                    // set parameter variable for each valid parameter
+                    match param.name.as_str() {
+                        | "max_width" => match param.value.parse() {
+                            | Ok(value) => max_width = value,
+                            | Err(_) => {
+                                errors.push(error(param.column, invalid_value))
+                            }
+                        },
+                        | "filter" => match param.value.parse() {
+                            | Ok(value) => filter = value,
+                            | Err(_) => {
+                                errors.push(error(param.column, invalid_value))
+                            }
+                        },
+                        | "case" => match param.value.parse() {
+                            | Ok(value) => case = value,
+                            | Err(_) => {
+                                errors.push(error(param.column, invalid_value))
+                            }
+                        },
+                        | "normalize" => match param.value.parse() {
+                            | Ok(value) => normalize = value,
+                            | Err(_) => {
+                                errors.push(error(param.column, invalid_value))
+                            }
+                        },
+                        | "name" => name = param.value,
+                        | "label" => label = param.value,
+                        | "placeholder" => placeholder = param.value,
+                        | "content" => content = param.value,
+                        | "fg" | "bg" | "bold" | "dim" | "underline" => {
+                            if style.set(&param.name, &param.value).is_err() {
+                                errors.push(error(param.column, invalid_value))
+                            }
+                        }
+                        | _ => {}
                     }
-                    | "label" => label = value.to_string(),
-                    | "placeholder" => placeholder = value.to_string(),
-                    | "content" => content = value.to_string(),
-                    | _ => {}
                 }
+
+                // add a new input widget to the model
+                widgets.push(Widget::Input {
+                    y: level,
+                    max_width,
+                    filter,
+                    case,
+                    normalize,
+                    error: None,
+                    name,
+                    label,
+                    placeholder,
+                    content,
+                    style,
+                    id: widgets.len(),
+                });
             }
+            | Ok(Some(element)) if element.name == "text" => {
+                // found text widget, its value defaults to the displayed content
+                let mut value = None;
+                let mut key = String::new();
+                let mut style = Style::default();
+
+                for param in element.params {
+                    match param.name.as_str() {
+                        | "value" => value = Some(param.value),
+                        | "key" => key = param.value,
+                        | "fg" | "bg" | "bold" | "dim" | "underline" => {
+                            if style.set(&param.name, &param.value).is_err() {
+                                errors.push(error(
+                                    param.column,
+                                    ParseErrorKind::InvalidValue {
+                                        param: param.name,
+                                        value: param.value,
+                                    },
+                                ))
+                            }
+                        }
+                        | _ => {}
+                    }
+                }
 
-            // add a new input widget to the model
-            widgets.push(Widget::Input {
-                y: level,
-                max_width,
-                filter,
-                label,
-                placeholder,
-                content,
-                id: unique_id,
-            });
-        } else if let Some(captures) = text_regex.captures(line) {
-            // found text widget, identify its text content
-            let content: String = captures[1].to_string();
-
-            // add a new text widget to the model
-            widgets.push(Widget::Text {
-                y: level,
-                content,
-                show: true,
-                id: unique_id,
-            });
-        } else if let Some(captures) = data_regex.captures(line) {
-            // found data widget, set the data variable to its content
-            let content: String = captures[1].to_string();
-            data = content.clone();
-            level -= 1; // TODO: this is a temp fix for a later day...
-            unique_id -= 1;
-        } else if let Some(captures) = quit_regex.captures(line) {
-            // found quit widget, clear the model and stop reading stdout
-            if let Some(content) = captures.get(1) {
-                let command = content.as_str().to_string();
-                exec::spawn_detached_child(&command);
+                match element.content {
+                    | Some(content) => widgets.push(Widget::Text {
+                        y: level,
+                        value: value.unwrap_or_else(|| content.clone()),
+                        content,
+                        key,
+                        show: true,
+                        marked: false,
+                        matches: Vec::new(),
+                        style,
+                        id: widgets.len(),
+                    }),
+                    | None => errors.push(error(
+                        element.column,
+                        ParseErrorKind::MissingArgument("content".to_string()),
+                    )),
+                }
             }
-            widgets.clear();
-            break;
+            | Ok(Some(element)) if element.name == "checkbox" => {
+                // found checkbox widget, its name defaults to the label
+                let mut label = String::new();
+                let mut name = None;
+                let mut checked = false;
+
+                for param in element.params {
+                    match param.name.as_str() {
+                        | "label" => label = param.value,
+                        | "name" => name = Some(param.value),
+                        | "checked" => match param.value.parse() {
+                            | Ok(value) => checked = value,
+                            | Err(_) => errors.push(error(
+                                param.column,
+                                ParseErrorKind::InvalidValue {
+                                    param: param.name,
+                                    value: param.value,
+                                },
+                            )),
+                        },
+                        | _ => {}
+                    }
+                }
+
+                widgets.push(Widget::Checkbox {
+                    y: level,
+                    name: name.unwrap_or_else(|| label.clone()),
+                    label,
+                    checked,
+                    id: widgets.len(),
+                });
+            }
+            | Ok(Some(element)) if element.name == "select" => {
+                // found select widget, its name defaults to the label and selection to the first option
+                let mut label = String::new();
+                let mut name = None;
+                let mut options = Vec::new();
+                let mut selected = None;
+
+                for param in element.params {
+                    match param.name.as_str() {
+                        | "label" => label = param.value,
+                        | "name" => name = Some(param.value),
+                        | "options" => {
+                            options = param.items.unwrap_or_else(|| {
+                                param
+                                    .value
+                                    .split('|')
+                                    .map(|option| option.to_string())
+                                    .collect()
+                            })
+                        }
+                        | "selected" => selected = Some(param),
+                        | _ => {}
+                    }
+                }
+
+                if options.is_empty() {
+                    errors.push(error(
+                        element.column,
+                        ParseErrorKind::MissingArgument("options".to_string()),
+                    ));
+                }
+
+                let selected = match selected {
+                    | Some(param) => {
+                        match options.iter().position(|o| *o == param.value) {
+                            | Some(index) => index,
+                            | None => {
+                                errors.push(error(
+                                    param.column,
+                                    ParseErrorKind::InvalidValue {
+                                        param: param.name,
+                                        value: param.value,
+                                    },
+                                ));
+                                0
+                            }
+                        }
+                    }
+                    | None => 0,
+                };
+
+                widgets.push(Widget::Select {
+                    y: level,
+                    name: name.unwrap_or_else(|| label.clone()),
+                    label,
+                    options,
+                    selected,
+                    id: widgets.len(),
+                });
+            }
+            | Ok(Some(element)) if element.name == "data" => {
+                // found data widget, set the data variable to its content
+                match element.content {
+                    | Some(content) => data = content,
+                    | None => errors.push(error(
+                        element.column,
+                        ParseErrorKind::MissingArgument("content".to_string()),
+                    )),
+                }
+                takes_row = false;
+            }
+            | Ok(Some(element)) if element.name == "multiselect" => {
+                // found multi-select directive, it takes no space in the model
+                multi_select = true;
+                takes_row = false;
+            }
+            | Ok(Some(element)) if element.name == "quit" => {
+                // found quit widget, clear the model and stop reading stdout
+                quit_command = Some(element.content);
+                widgets.clear();
+                break;
+            }
+            | Ok(Some(element)) => errors.push(error(
+                element.column,
+                ParseErrorKind::UnknownWidget(element.name),
+            )),
+            | Ok(None) => {}
+            | Err((column, kind)) => errors.push(error(column, kind)),
         }
 
-        level += 1;
-        unique_id += 1; // TODO: remove id & just use index
+        if takes_row {
+            level += 1;
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(Screen {
+        widgets,
+        data,
+        multi_select,
+        quit_command: quit_command.flatten(),
+    })
+}
+
+/// Decode a single line of macro protocol stdout, see `parse_stdout_as`.
+///
+/// Blank lines decode to `None`.
+fn parse_macro_line(
+    line: &str,
+) -> Result<Option<Element>, (usize, ParseErrorKind)> {
+    if line.trim().is_empty() {
+        return Ok(None);
     }
 
-    (widgets, data)
+    let parsed =
+        parse_macro(line).map_err(|error| (error.column, error.kind))?;
+
+    Ok(Some(Element {
+        name: parsed.name.to_lowercase(),
+        content: parsed.positional().map(|arg| arg.value.clone()),
+        params: parsed
+            .params()
+            .map(|arg| Param {
+                name: arg.name.clone().unwrap_or_default(),
+                value: arg.value.clone(),
+                items: None,
+                column: arg.column,
+            })
+            .collect(),
+        column: parsed.column,
+    }))
+}
+
+/// Decode a single line of json protocol stdout, see `parse_stdout_as`.
+///
+/// Blank lines decode to `None`.
+fn parse_json_line(
+    line: &str,
+) -> Result<Option<Element>, (usize, ParseErrorKind)> {
+    if line.trim().is_empty() {
+        return Ok(None);
+    }
+
+    // column of the start of the object, (1-based, as columns are reported)
+    let start = line.chars().take_while(|c| c.is_whitespace()).count() + 1;
+
+    let object = match serde_json::from_str::<Value>(line) {
+        | Ok(Value::Object(object)) => object,
+        | Ok(_) => {
+            return Err((
+                start,
+                ParseErrorKind::InvalidJson("expected an object".to_string()),
+            ))
+        }
+        | Err(error) => {
+            return Err((
+                error.column().max(1),
+                ParseErrorKind::InvalidJson(error.to_string()),
+            ))
+        }
+    };
+
+    // json values are stringified so both protocols share parameter parsing,
+    // (arrays are joined the way SELECT options are written, and kept as items)
+    let array_field = |key: &str| match object.get(key) {
+        | Some(Value::Array(items)) => Some(
+            items
+                .iter()
+                .map(|item| match item {
+                    | Value::String(s) => s.clone(),
+                    | other => other.to_string(),
+                })
+                .collect::<Vec<_>>(),
+        ),
+        | _ => None,
+    };
+
+    let string_field = |key: &str| match object.get(key) {
+        | Some(Value::String(s)) => Some(s.clone()),
+        | Some(Value::Number(n)) => Some(n.to_string()),
+        | Some(Value::Bool(b)) => Some(b.to_string()),
+        | Some(Value::Array(_)) => {
+            array_field(key).map(|items| items.join("|"))
+        }
+        | _ => None,
+    };
+
+    let name = match object.get("type") {
+        | Some(Value::String(name)) => name.to_lowercase(),
+        | _ => {
+            return Err((
+                key_column(line, "type").unwrap_or(start),
+                ParseErrorKind::MissingArgument("type".to_string()),
+            ))
+        }
+    };
+
+    let content = if name == "quit" {
+        string_field("command")
+    } else {
+        string_field("content")
+    };
+
+    Ok(Some(Element {
+        params: object
+            .keys()
+            .filter(|key| key.as_str() != "type")
+            .filter_map(|key| {
+                Some(Param {
+                    name: key.clone(),
+                    value: string_field(key)?,
+                    items: array_field(key),
+                    column: key_column(line, key).unwrap_or(start),
+                })
+            })
+            .collect(),
+        name,
+        content,
+        column: key_column(line, "type").unwrap_or(start),
+    }))
+}
+
+/// Return the column of key in a line of json, (1-based), where it is written as a key followed by
+/// `:`, and `None` if it is not.
+fn key_column(line: &str, key: &str) -> Option<usize> {
+    let quoted = serde_json::to_string(key).ok()?;
+    line.match_indices(&quoted)
+        .find(|(index, _)| {
+            line[index + quoted.len()..].trim_start().starts_with(':')
+        })
+        .map(|(index, _)| line[..index].chars().count() + 1)
 }
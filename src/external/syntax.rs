@@ -0,0 +1,285 @@
+use crate::utils::error::{ParseError, ParseErrorKind};
+
+/// A single macro call read from a line of stdout, e.g. `INPUT(label="foo", max_width=20)`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Macro {
+    pub name: String,
+    pub args: Vec<Arg>,
+    pub column: usize,
+}
+
+/// An argument of a macro call, named when written as `name=value` and positional otherwise.
+///
+/// `column` is where the argument starts in the line, (1-based).
+#[derive(Debug, PartialEq, Clone)]
+pub struct Arg {
+    pub name: Option<String>,
+    pub value: String,
+    pub column: usize,
+}
+
+impl Macro {
+    /// Return the first positional argument, if any.
+    pub fn positional(&self) -> Option<&Arg> {
+        self.args.iter().find(|arg| arg.name.is_none())
+    }
+
+    /// Iterate over the named arguments.
+    pub fn params(&self) -> impl Iterator<Item = &Arg> {
+        self.args.iter().filter(|arg| arg.name.is_some())
+    }
+}
+
+/// Escape a string so it can be written between the quotes of a macro argument.
+///
+/// The inverse of the escape sequences accepted by `parse_macro`.
+pub fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            | '"' => escaped.push_str("\\\""),
+            | '\\' => escaped.push_str("\\\\"),
+            | '\n' => escaped.push_str("\\n"),
+            | '\t' => escaped.push_str("\\t"),
+            | '\r' => escaped.push_str("\\r"),
+            | c if c.is_control() => {
+                escaped.push_str(&format!("\\u{{{:x}}}", c as u32))
+            }
+            | c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Parse a line of stdout as a single macro call.
+///
+/// Returns a `ParseError` pointing at the offending column if the line is not a well formed macro,
+/// see the BNF form in `external::model::parse_stdout_as`. The error is reported on line 1,
+/// callers parsing many lines set the line number themselves.
+pub fn parse_macro(line: &str) -> Result<Macro, ParseError> {
+    let mut cursor = Cursor {
+        chars: line.chars().collect(),
+        pos: 0,
+    };
+
+    cursor.parse_macro().map_err(|(column, kind)| ParseError {
+        line: 1,
+        column,
+        kind,
+        snippet: line.to_string(),
+    })
+}
+
+type SyntaxResult<T> = Result<T, (usize, ParseErrorKind)>;
+
+/// Position in a line of stdout being parsed.
+struct Cursor {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Cursor {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    /// Current 1-based column.
+    fn column(&self) -> usize {
+        self.pos + 1
+    }
+
+    fn error<T>(&self, kind: ParseErrorKind) -> SyntaxResult<T> {
+        Err((self.column(), kind))
+    }
+
+    fn skip_while(&mut self, predicate: impl Fn(char) -> bool) {
+        while matches!(self.peek(), Some(c) if predicate(c)) {
+            self.pos += 1;
+        }
+    }
+
+    fn identifier(&mut self) -> String {
+        let mut identifier = String::new();
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_alphanumeric() || c == '_') {
+                break;
+            }
+            identifier.push(c);
+            self.pos += 1;
+        }
+        identifier
+    }
+
+    fn expect(&mut self, expected: char) -> SyntaxResult<()> {
+        match self.peek() {
+            | Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            | Some(_) => self.error(ParseErrorKind::Expected(expected)),
+            | None => self.error(ParseErrorKind::UnexpectedEnd),
+        }
+    }
+
+    fn parse_macro(&mut self) -> SyntaxResult<Macro> {
+        self.skip_while(char::is_whitespace);
+
+        // macro name, up to the opening parenthesis
+        let column = self.column();
+        let name = self.identifier();
+        if name.is_empty() {
+            return self.error(ParseErrorKind::ExpectedMacro);
+        }
+        self.expect('(')?;
+
+        let mut args = Vec::new();
+
+        loop {
+            // skip separators between arguments
+            self.skip_while(|c| c.is_whitespace() || c == ',');
+
+            let column = self.column();
+            match self.peek() {
+                | Some(')') => {
+                    self.pos += 1;
+                    break;
+                }
+                | Some('"') => {
+                    // positional argument
+                    let value = self.parse_string()?;
+                    args.push(Arg {
+                        name: None,
+                        value,
+                        column,
+                    });
+                }
+                | Some(_) => {
+                    // named argument, name '=' value
+                    let param = self.identifier();
+                    if param.is_empty() {
+                        return self.error(ParseErrorKind::Expected(')'));
+                    }
+                    self.skip_while(char::is_whitespace);
+                    self.expect('=')?;
+                    self.skip_while(char::is_whitespace);
+
+                    let value = if self.peek() == Some('"') {
+                        self.parse_string()?
+                    } else {
+                        // unquoted values run up to the next separator
+                        let mut value = String::new();
+                        while let Some(c) = self.peek() {
+                            if c.is_whitespace() || matches!(c, ',' | ')' | '"')
+                            {
+                                break;
+                            }
+                            value.push(c);
+                            self.pos += 1;
+                        }
+                        value
+                    };
+
+                    args.push(Arg {
+                        name: Some(param),
+                        value,
+                        column,
+                    });
+                }
+                | None => return self.error(ParseErrorKind::UnexpectedEnd),
+            }
+        }
+
+        // nothing but whitespace may follow the closing parenthesis
+        self.skip_while(char::is_whitespace);
+        if self.peek().is_some() {
+            return self.error(ParseErrorKind::TrailingCharacters);
+        }
+
+        Ok(Macro { name, args, column })
+    }
+
+    /// Read a quoted string starting at the opening quote, resolving escapes.
+    fn parse_string(&mut self) -> SyntaxResult<String> {
+        let start = self.column();
+        let mut value = String::new();
+        self.expect('"')?;
+
+        loop {
+            let escape_column = self.column();
+            match self.next() {
+                | None => {
+                    return Err((start, ParseErrorKind::UnterminatedString))
+                }
+                | Some('"') => return Ok(value),
+                | Some('\\') => match self.next() {
+                    | Some('"') => value.push('"'),
+                    | Some('\\') => value.push('\\'),
+                    | Some('n') => value.push('\n'),
+                    | Some('t') => value.push('\t'),
+                    | Some('r') => value.push('\r'),
+                    | Some('u') => match self.parse_unicode_escape() {
+                        | Some(c) => value.push(c),
+                        | None => {
+                            let escape = self.chars[escape_column - 1
+                                ..self.pos.min(self.chars.len())]
+                                .iter()
+                                .collect();
+                            return Err((
+                                escape_column,
+                                ParseErrorKind::InvalidEscape(escape),
+                            ));
+                        }
+                    },
+                    | Some(c) => {
+                        return Err((
+                            escape_column,
+                            ParseErrorKind::InvalidEscape(format!("\\{}", c)),
+                        ))
+                    }
+                    | None => {
+                        return Err((start, ParseErrorKind::UnterminatedString))
+                    }
+                },
+                | Some(c) => value.push(c),
+            }
+        }
+    }
+
+    /// Read the code point of a `\uXXXX` or `\u{X..}` escape, (the `\u` already consumed).
+    fn parse_unicode_escape(&mut self) -> Option<char> {
+        let mut hex = String::new();
+
+        if self.peek() == Some('{') {
+            // braced form, 1 to 6 hex digits
+            self.pos += 1;
+            loop {
+                match self.next()? {
+                    | '}' => break,
+                    | c if c.is_ascii_hexdigit() && hex.len() < 6 => {
+                        hex.push(c)
+                    }
+                    | _ => return None,
+                }
+            }
+        } else {
+            // short form, exactly 4 hex digits
+            for _ in 0..4 {
+                let c = self.next()?;
+                if !c.is_ascii_hexdigit() {
+                    return None;
+                }
+                hex.push(c);
+            }
+        }
+
+        u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+    }
+}
@@ -1,3 +1,4 @@
+use crate::interface::theme::Style;
 use std::str::FromStr;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -5,6 +6,9 @@ pub enum Filter {
     Off,
     Exact,
     Fuzzy,
+    Regex,
+    Prefix,
+    Glob,
 }
 
 impl FromStr for Filter {
@@ -15,6 +19,32 @@ impl FromStr for Filter {
             | "off" => Ok(Filter::Off),
             | "exact" => Ok(Filter::Exact),
             | "fuzzy" => Ok(Filter::Fuzzy),
+            | "regex" => Ok(Filter::Regex),
+            | "prefix" => Ok(Filter::Prefix),
+            | "glob" => Ok(Filter::Glob),
+            | _ => Err(()),
+        }
+    }
+}
+
+/// How an input compares letter case when filtering.
+///
+/// Smart case matches case-insensitively unless the search string contains an uppercase letter.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Case {
+    Smart,
+    Sensitive,
+    Insensitive,
+}
+
+impl FromStr for Case {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            | "smart" => Ok(Case::Smart),
+            | "sensitive" => Ok(Case::Sensitive),
+            | "insensitive" => Ok(Case::Insensitive),
             | _ => Err(()),
         }
     }
@@ -26,15 +56,40 @@ pub enum Widget {
         y: i32,
         max_width: usize,
         filter: Filter,
+        case: Case,
+        normalize: bool,
+        error: Option<String>,
+        name: String,
         label: String,
         placeholder: String,
         content: String,
+        style: Style,
         id: usize,
     },
     Text {
         y: i32,
         content: String,
+        value: String,
+        key: String,
         show: bool,
+        marked: bool,
+        matches: Vec<usize>,
+        style: Style,
+        id: usize,
+    },
+    Select {
+        y: i32,
+        label: String,
+        name: String,
+        options: Vec<String>,
+        selected: usize,
+        id: usize,
+    },
+    Checkbox {
+        y: i32,
+        label: String,
+        name: String,
+        checked: bool,
         id: usize,
     },
 }
@@ -0,0 +1,823 @@
+extern crate pancurses;
+
+use crate::interface::theme::Style;
+use crate::utils::text::{char_width, display_width};
+use pancurses::{
+    chtype, curs_set, endwin, getmouse, has_colors, init_pair, initscr,
+    mouseinterval, mousemask, noecho, start_color, use_default_colors, Input,
+    Window, A_BOLD, A_DIM, A_NORMAL, A_UNDERLINE, BUTTON1_PRESSED,
+    BUTTON4_PRESSED, BUTTON5_PRESSED, COLORS, COLOR_PAIR, COLOR_PAIRS,
+};
+use regex::Regex;
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::mem;
+use std::os::unix::io::AsRawFd;
+use std::str::FromStr;
+
+/// Mouse event, (a button pressed at a terminal cell or the wheel turned).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Mouse {
+    Click { y: i32, x: i32 },
+    ScrollUp,
+    ScrollDown,
+}
+
+/// Terminal the ui is drawn to and reads key events from.
+///
+/// Rows and columns are 0-based, key events are pancurses `Input` values whatever the backend.
+pub trait Backend {
+    /// Return the size of the terminal as `(rows, columns)`.
+    fn size(&self) -> (i32, i32);
+
+    /// Clear the whole terminal.
+    fn clear(&mut self);
+
+    /// Write s starting at row y and column x, (in the terminal's colours).
+    fn print(&mut self, y: i32, x: i32, s: &str) {
+        self.print_styled(y, x, s, Style::default());
+    }
+
+    /// Write s starting at row y and column x in the given style.
+    fn print_styled(&mut self, y: i32, x: i32, s: &str, style: Style);
+
+    /// Clear row y from column x to its end.
+    fn clear_to_eol(&mut self, y: i32, x: i32);
+
+    /// Move the cursor to row y and column x.
+    fn move_cursor(&mut self, y: i32, x: i32);
+
+    /// Show or hide the cursor.
+    fn show_cursor(&mut self, visible: bool);
+
+    /// Flush pending changes to the terminal.
+    fn refresh(&mut self);
+
+    /// Return the next key event, (or `None` if there is none yet).
+    fn read_key(&mut self) -> Option<Input>;
+
+    /// Return the mouse event reported by the last `Input::KeyMouse` read, (`None` if it is not one the
+    /// ui handles).
+    fn read_mouse(&mut self) -> Option<Mouse>;
+}
+
+/// Ncurses terminal, ended when dropped.
+///
+/// Each pair of colours text is drawn in is given a curses colour pair the first time it is used.
+pub struct Curses {
+    window: Window,
+    pairs: HashMap<(i16, i16), i16>,
+}
+
+impl Curses {
+    /// Initialize ncurses and its standard window.
+    pub fn new() -> Curses {
+        let window = initscr();
+        window.keypad(true);
+        window.nodelay(true);
+        window.timeout(0);
+        noecho();
+        curs_set(1);
+        if has_colors() {
+            // colours default to the terminal's own, (-1 in a colour pair)
+            start_color();
+            use_default_colors();
+        }
+
+        Curses {
+            window,
+            pairs: HashMap::new(),
+        }
+    }
+
+    /// Return the colour pair for the colours of style, (0, the terminal's own, if there are no pairs
+    /// left or the terminal has fewer colours).
+    fn pair(&mut self, style: Style) -> i16 {
+        if !style.has_color() || !has_colors() {
+            return 0;
+        }
+        let fg = style.fg.map_or(-1, |color| color.number());
+        let bg = style.bg.map_or(-1, |color| color.number());
+        if fg as i32 >= COLORS() || bg as i32 >= COLORS() {
+            return 0;
+        }
+
+        let next = self.pairs.len() as i16 + 1;
+        match self.pairs.get(&(fg, bg)) {
+            | Some(pair) => *pair,
+            | None if (next as i32) < COLOR_PAIRS() => {
+                init_pair(next, fg, bg);
+                self.pairs.insert((fg, bg), next);
+                next
+            }
+            | None => 0,
+        }
+    }
+
+    /// Report left clicks and the wheel as `Input::KeyMouse` events, (read with `read_mouse`).
+    pub fn enable_mouse(&mut self) {
+        mousemask(BUTTON1_PRESSED | BUTTON4_PRESSED | BUTTON5_PRESSED, None);
+        // report presses right away, instead of waiting to tell clicks from double clicks
+        mouseinterval(0);
+    }
+}
+
+impl Default for Curses {
+    fn default() -> Self {
+        Curses::new()
+    }
+}
+
+impl Drop for Curses {
+    /// Restore the terminal.
+    fn drop(&mut self) {
+        endwin();
+    }
+}
+
+impl Backend for Curses {
+    fn size(&self) -> (i32, i32) {
+        self.window.get_max_yx()
+    }
+
+    fn clear(&mut self) {
+        self.window.clear();
+    }
+
+    fn print_styled(&mut self, y: i32, x: i32, s: &str, style: Style) {
+        let mut attributes: chtype = COLOR_PAIR(self.pair(style) as chtype);
+        for (set, attribute) in [
+            (style.bold, A_BOLD),
+            (style.dim, A_DIM),
+            (style.underline, A_UNDERLINE),
+        ] {
+            if set == Some(true) {
+                attributes |= attribute;
+            }
+        }
+
+        self.window.attrset(attributes);
+        self.window.mvprintw(y, x, s);
+        self.window.attrset(A_NORMAL);
+    }
+
+    fn clear_to_eol(&mut self, y: i32, x: i32) {
+        self.window.mv(y, x);
+        self.window.clrtoeol();
+    }
+
+    fn move_cursor(&mut self, y: i32, x: i32) {
+        self.window.mv(y, x);
+    }
+
+    fn show_cursor(&mut self, visible: bool) {
+        curs_set(if visible { 1 } else { 0 });
+    }
+
+    fn refresh(&mut self) {
+        self.window.refresh();
+    }
+
+    fn read_key(&mut self) -> Option<Input> {
+        self.window.getch()
+    }
+
+    fn read_mouse(&mut self) -> Option<Mouse> {
+        let event = getmouse().ok()?;
+        if event.bstate & BUTTON1_PRESSED != 0 {
+            Some(Mouse::Click {
+                y: event.y,
+                x: event.x,
+            })
+        } else if event.bstate & BUTTON4_PRESSED != 0 {
+            Some(Mouse::ScrollUp)
+        } else if event.bstate & BUTTON5_PRESSED != 0 {
+            Some(Mouse::ScrollDown)
+        } else {
+            None
+        }
+    }
+}
+
+/// Cells of a terminal kept in memory, (what the in-memory backends print to).
+///
+/// Wide characters take up 2 cells, (the second left empty), and zero width ones join the cell
+/// before them, as on a terminal. Control characters are dropped, (they take up no columns in the
+/// layout and must not reach the terminal).
+#[derive(Debug, Clone)]
+struct Grid {
+    cells: Vec<Vec<(String, Style)>>,
+    cursor: (i32, i32),
+}
+
+impl Grid {
+    /// Create an empty grid of the given size.
+    fn new(rows: usize, columns: usize) -> Grid {
+        Grid {
+            cells: vec![
+                vec![(" ".to_string(), Style::default()); columns];
+                rows
+            ],
+            cursor: (0, 0),
+        }
+    }
+
+    fn size(&self) -> (i32, i32) {
+        let columns = self.cells.first().map_or(0, Vec::len);
+        (self.cells.len() as i32, columns as i32)
+    }
+
+    /// Change the size of the grid, (cells cut off are lost, new ones are blank).
+    fn resize(&mut self, rows: usize, columns: usize) {
+        let blank = (" ".to_string(), Style::default());
+        self.cells.resize(rows, vec![blank.clone(); columns]);
+        for row in &mut self.cells {
+            row.resize(columns, blank.clone());
+        }
+    }
+
+    fn clear(&mut self) {
+        for row in &mut self.cells {
+            row.fill((" ".to_string(), Style::default()));
+        }
+    }
+
+    /// Return the mutable cell at row y and column x, (if it is on the grid).
+    fn cell(&mut self, y: i32, x: i32) -> Option<&mut (String, Style)> {
+        if y < 0 || x < 0 {
+            return None;
+        }
+        self.cells
+            .get_mut(y as usize)
+            .and_then(|row| row.get_mut(x as usize))
+    }
+
+    fn print_styled(&mut self, y: i32, x: i32, s: &str, style: Style) {
+        // characters past the edge of the grid are dropped
+        let mut column = x;
+        for c in s.chars().filter(|c| !c.is_control()) {
+            let width = char_width(c) as i32;
+            if width == 0 {
+                if let Some(cell) = self.cell(y, column - 1) {
+                    cell.0.push(c);
+                }
+                continue;
+            }
+            if let Some(cell) = self.cell(y, column) {
+                *cell = (c.to_string(), style);
+            }
+            for covered in column + 1..column + width {
+                if let Some(cell) = self.cell(y, covered) {
+                    *cell = (String::new(), style);
+                }
+            }
+            column += width;
+        }
+        self.cursor = (y, x + display_width(s) as i32);
+    }
+
+    fn clear_to_eol(&mut self, y: i32, x: i32) {
+        let (_, columns) = self.size();
+        for column in x..columns {
+            if let Some(cell) = self.cell(y, column) {
+                *cell = (" ".to_string(), Style::default());
+            }
+        }
+        self.cursor = (y, x);
+    }
+}
+
+/// In-memory terminal recording a grid of cells and replaying scripted key events.
+///
+/// Once the script runs out every read returns `Input::KeyClose`, (which quits the ui). Reading an
+/// `Input::KeyResize` resizes the grid to the next queued size, as ncurses does before it reports a
+/// resize, and each `Input::KeyMouse` reports the next queued mouse event.
+#[derive(Debug)]
+pub struct Headless {
+    grid: Grid,
+    cursor_visible: bool,
+    keys: VecDeque<Input>,
+    sizes: VecDeque<(usize, usize)>,
+    mouse: VecDeque<Mouse>,
+}
+
+impl Headless {
+    /// Create an empty terminal of the given size that will read the given keys.
+    pub fn new(
+        rows: usize,
+        columns: usize,
+        keys: impl IntoIterator<Item = Input>,
+    ) -> Headless {
+        Headless {
+            grid: Grid::new(rows, columns),
+            cursor_visible: true,
+            keys: keys.into_iter().collect(),
+            sizes: VecDeque::new(),
+            mouse: VecDeque::new(),
+        }
+    }
+
+    /// Queue a size for the terminal to take when the next `Input::KeyResize` is read.
+    pub fn queue_resize(&mut self, rows: usize, columns: usize) {
+        self.sizes.push_back((rows, columns));
+    }
+
+    /// Queue a mouse event for the next `Input::KeyMouse` read to report.
+    pub fn queue_mouse(&mut self, mouse: Mouse) {
+        self.mouse.push_back(mouse);
+    }
+
+    /// Return the text of row y without trailing whitespace.
+    pub fn row(&self, y: usize) -> String {
+        self.grid.cells[y]
+            .iter()
+            .map(|(cell, _)| cell.as_str())
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    /// Return the characters of row y drawn in the given style.
+    pub fn styled(&self, y: usize, style: Style) -> String {
+        self.grid.cells[y]
+            .iter()
+            .filter(|(_, cell_style)| *cell_style == style)
+            .map(|(cell, _)| cell.as_str())
+            .collect()
+    }
+
+    /// Return the text of every row, newline deliminated, without trailing whitespace or empty rows.
+    pub fn snapshot(&self) -> String {
+        (0..self.grid.cells.len())
+            .map(|y| self.row(y))
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim_end()
+            .to_string()
+    }
+
+    /// Return the cursor position as `(row, column)`.
+    pub fn cursor(&self) -> (i32, i32) {
+        self.grid.cursor
+    }
+
+    /// Return `true` if the cursor is shown.
+    pub fn cursor_visible(&self) -> bool {
+        self.cursor_visible
+    }
+}
+
+impl Backend for Headless {
+    fn size(&self) -> (i32, i32) {
+        self.grid.size()
+    }
+
+    fn clear(&mut self) {
+        self.grid.clear();
+    }
+
+    fn print_styled(&mut self, y: i32, x: i32, s: &str, style: Style) {
+        self.grid.print_styled(y, x, s, style);
+    }
+
+    fn clear_to_eol(&mut self, y: i32, x: i32) {
+        self.grid.clear_to_eol(y, x);
+    }
+
+    fn move_cursor(&mut self, y: i32, x: i32) {
+        self.grid.cursor = (y, x);
+    }
+
+    fn show_cursor(&mut self, visible: bool) {
+        self.cursor_visible = visible;
+    }
+
+    fn refresh(&mut self) {}
+
+    fn read_key(&mut self) -> Option<Input> {
+        let key = self.keys.pop_front().unwrap_or(Input::KeyClose);
+        if key == Input::KeyResize {
+            if let Some((rows, columns)) = self.sizes.pop_front() {
+                self.grid.resize(rows, columns);
+            }
+        }
+        Some(key)
+    }
+
+    fn read_mouse(&mut self) -> Option<Mouse> {
+        self.mouse.pop_front()
+    }
+}
+
+/// Height of an inline terminal, a number of rows or a percentage of the terminal's rows.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Height {
+    Rows(usize),
+    Percent(usize),
+}
+
+impl Height {
+    /// Return the number of rows taken up in a terminal with the given rows, (at least 1 and at
+    /// most all of them).
+    pub fn rows(&self, terminal_rows: usize) -> usize {
+        let rows = match self {
+            | Height::Rows(rows) => *rows,
+            | Height::Percent(percent) => terminal_rows * percent / 100,
+        };
+        rows.clamp(1, terminal_rows.max(1))
+    }
+}
+
+impl FromStr for Height {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_suffix('%') {
+            | Some(percent) => match percent.parse() {
+                | Ok(percent) if percent <= 100 => Ok(Height::Percent(percent)),
+                | _ => Err(()),
+            },
+            | None => s.parse().map(Height::Rows).map_err(|_| ()),
+        }
+    }
+}
+
+/// Decode the first key in the bytes a terminal sent, returning it, the mouse event it reports,
+/// (if it is an `Input::KeyMouse`, at 0-based terminal coordinates), and the number of bytes it
+/// took up.
+///
+/// Escape sequences are expected to arrive whole, so an escape ending the bytes is the escape key.
+/// Characters may not, the start of one ending the bytes is decoded to no key taking up no bytes,
+/// (to be decoded again once the rest is read).
+/// Unknown sequences and invalid characters are skipped, (decoded to no key), and ctrl-c is read as
+/// `Input::KeyCancel`, (the terminal does not send an interrupt in raw mode).
+pub fn decode_key(bytes: &[u8]) -> (Option<Input>, Option<Mouse>, usize) {
+    match bytes {
+        | [] => (None, None, 0),
+        | [0x1b, b'[', rest @ ..] => {
+            // control sequence, parameter bytes up to a final byte
+            let end = match rest.iter().position(|b| (0x40..0x7f).contains(b)) {
+                | Some(end) => end,
+                | None => return (None, None, bytes.len()),
+            };
+            let params = String::from_utf8_lossy(&rest[..end]);
+            let length = end + 3;
+            let input = match (rest[end], params.as_ref()) {
+                | (b'A', _) => Input::KeyUp,
+                | (b'B', _) => Input::KeyDown,
+                | (b'C', _) => Input::KeyRight,
+                | (b'D', _) => Input::KeyLeft,
+                | (b'H', _) => Input::KeyHome,
+                | (b'F', _) => Input::KeyEnd,
+                | (b'Z', _) => Input::KeySTab,
+                | (b'~', "1" | "7") => Input::KeyHome,
+                | (b'~', "2") => Input::KeyIC,
+                | (b'~', "3") => Input::KeyDC,
+                | (b'~', "4" | "8") => Input::KeyEnd,
+                | (b'~', "5") => Input::KeyPPage,
+                | (b'~', "6") => Input::KeyNPage,
+                | (b'M', params) if params.starts_with('<') => {
+                    // SGR mouse report of a button pressed, `<button;column;row`, (1-based)
+                    let fields: Vec<i32> = params[1..]
+                        .split(';')
+                        .filter_map(|field| field.parse().ok())
+                        .collect();
+                    let mouse = match fields[..] {
+                        | [64, _, _] => Some(Mouse::ScrollUp),
+                        | [65, _, _] => Some(Mouse::ScrollDown),
+                        | [0, x, y] => {
+                            Some(Mouse::Click { y: y - 1, x: x - 1 })
+                        }
+                        | _ => None,
+                    };
+                    return match mouse {
+                        | Some(mouse) => {
+                            (Some(Input::KeyMouse), Some(mouse), length)
+                        }
+                        | None => (None, None, length),
+                    };
+                }
+                | _ => return (None, None, length),
+            };
+            (Some(input), None, length)
+        }
+        | [0x1b, b'O', key, ..] => {
+            // application mode cursor keys
+            let input = match key {
+                | b'A' => Input::KeyUp,
+                | b'B' => Input::KeyDown,
+                | b'C' => Input::KeyRight,
+                | b'D' => Input::KeyLeft,
+                | b'H' => Input::KeyHome,
+                | b'F' => Input::KeyEnd,
+                | _ => return (None, None, 3),
+            };
+            (Some(input), None, 3)
+        }
+        | [0x03, ..] => (Some(Input::KeyCancel), None, 1),
+        | [byte, ..] if byte.is_ascii() => {
+            (Some(Input::Character(*byte as char)), None, 1)
+        }
+        | [lead, ..] => {
+            // multi-byte character, its length is given by the leading byte
+            let length = match lead.leading_ones() {
+                | 2..=4 => lead.leading_ones() as usize,
+                | _ => return (None, None, 1),
+            };
+            match std::str::from_utf8(&bytes[..length.min(bytes.len())]) {
+                | Ok(s) => {
+                    let c = s.chars().next().expect("character is not empty");
+                    (Some(Input::Character(c)), None, length)
+                }
+                // the rest of the character is still to be read
+                | Err(error) if error.error_len().is_none() => (None, None, 0),
+                | Err(_) => (None, None, 1),
+            }
+        }
+    }
+}
+
+/// Part of a terminal below the cursor drawn with ANSI escape sequences, (what came before it and
+/// the scrollback are left as they were).
+///
+/// The rows are reserved below the cursor when created, (scrolling the terminal if there are too
+/// few), and cleared when dropped, leaving the cursor where it started. Keys are read in raw mode
+/// from the controlling terminal and drawing goes to it, (so stdin and stdout can be redirected),
+/// only the rows changed since the last refresh are written.
+pub struct Inline {
+    tty: File,
+    saved: libc::termios,
+    height: Height,
+    reserved: usize,
+    terminal_size: (usize, usize),
+    grid: Grid,
+    shown: Vec<Vec<(String, Style)>>,
+    cursor_row: usize,
+    cursor_visible: bool,
+    top: Option<i32>,
+    unread: Vec<u8>,
+    mouse: Option<Mouse>,
+    mouse_enabled: bool,
+}
+
+impl Inline {
+    /// Reserve rows below the cursor of the controlling terminal and switch it to raw mode.
+    pub fn new(height: Height) -> io::Result<Inline> {
+        let tty = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+        let fd = tty.as_raw_fd();
+
+        // raw mode, reads wait a tenth of a second at most
+        let mut saved: libc::termios = unsafe { mem::zeroed() };
+        if unsafe { libc::tcgetattr(fd, &mut saved) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut raw = saved;
+        unsafe { libc::cfmakeraw(&mut raw) };
+        raw.c_cc[libc::VMIN] = 0;
+        raw.c_cc[libc::VTIME] = 1;
+        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let terminal_size = terminal_size(fd)?;
+        let rows = height.rows(terminal_size.0);
+        let mut inline = Inline {
+            tty,
+            saved,
+            height,
+            reserved: rows,
+            terminal_size,
+            grid: Grid::new(rows, terminal_size.1),
+            shown: Vec::new(),
+            cursor_row: 0,
+            cursor_visible: true,
+            top: None,
+            unread: Vec::new(),
+            mouse: None,
+            mouse_enabled: false,
+        };
+
+        // move down to the last row, (scrolling the terminal if it is past the bottom), and back up
+        let mut reserve = format!("\r{}", "\n".repeat(rows - 1));
+        if rows > 1 {
+            reserve.push_str(&format!("\x1b[{}A", rows - 1));
+        }
+        inline.write(&reserve)?;
+        inline.top = inline.cursor_position().map(|(row, _)| row);
+        Ok(inline)
+    }
+
+    /// Report left clicks and the wheel as `Input::KeyMouse` events, (read with `read_mouse`).
+    pub fn enable_mouse(&mut self) {
+        // report presses and releases, in the SGR format
+        if self.write("\x1b[?1000h\x1b[?1006h").is_ok() {
+            self.mouse_enabled = true;
+        }
+    }
+
+    /// Write s to the terminal right away.
+    fn write(&mut self, s: &str) -> io::Result<()> {
+        self.tty.write_all(s.as_bytes())?;
+        self.tty.flush()
+    }
+
+    /// Read the bytes the terminal sent, (none if it sent nothing for a tenth of a second).
+    fn read_bytes(&mut self) -> Vec<u8> {
+        let mut buffer = [0; 256];
+        match self.tty.read(&mut buffer) {
+            | Ok(length) => buffer[..length].to_vec(),
+            | Err(_) => Vec::new(),
+        }
+    }
+
+    /// Ask the terminal for the row and column of the cursor, (0-based).
+    ///
+    /// Keys typed before the answer are kept to be read as keys. Returns `None` if the terminal
+    /// does not answer within a second.
+    fn cursor_position(&mut self) -> Option<(i32, i32)> {
+        self.write("\x1b[6n").ok()?;
+        let report = Regex::new(r"\x1b\[(\d+);(\d+)R").expect("valid pattern");
+        for _ in 0..10 {
+            let bytes = self.read_bytes();
+            self.unread.extend(bytes);
+            let text = String::from_utf8_lossy(&self.unread).to_string();
+            if let Some(captures) = report.captures(&text) {
+                let whole = captures.get(0).expect("match has a whole group");
+                self.unread.drain(whole.range());
+                return Some((
+                    captures[1].parse::<i32>().ok()? - 1,
+                    captures[2].parse::<i32>().ok()? - 1,
+                ));
+            }
+        }
+        None
+    }
+
+    /// Append the sequence moving the terminal cursor to row y of the inline terminal.
+    fn move_to_row(&mut self, out: &mut String, y: usize) {
+        if y > self.cursor_row {
+            out.push_str(&format!("\x1b[{}B", y - self.cursor_row));
+        } else if y < self.cursor_row {
+            out.push_str(&format!("\x1b[{}A", self.cursor_row - y));
+        }
+        self.cursor_row = y;
+        out.push('\r');
+    }
+}
+
+impl Drop for Inline {
+    /// Clear the rows drawn to and restore the terminal.
+    fn drop(&mut self) {
+        let mut out = String::new();
+        self.move_to_row(&mut out, 0);
+        out.push_str("\x1b[0m\x1b[J\x1b[?25h");
+        if self.mouse_enabled {
+            out.push_str("\x1b[?1000l\x1b[?1006l");
+        }
+        let _ = self.write(&out);
+        unsafe {
+            libc::tcsetattr(self.tty.as_raw_fd(), libc::TCSANOW, &self.saved)
+        };
+    }
+}
+
+impl Backend for Inline {
+    fn size(&self) -> (i32, i32) {
+        self.grid.size()
+    }
+
+    fn clear(&mut self) {
+        self.grid.clear();
+    }
+
+    fn print_styled(&mut self, y: i32, x: i32, s: &str, style: Style) {
+        self.grid.print_styled(y, x, s, style);
+    }
+
+    fn clear_to_eol(&mut self, y: i32, x: i32) {
+        self.grid.clear_to_eol(y, x);
+    }
+
+    fn move_cursor(&mut self, y: i32, x: i32) {
+        self.grid.cursor = (y, x);
+    }
+
+    fn show_cursor(&mut self, visible: bool) {
+        self.cursor_visible = visible;
+    }
+
+    fn refresh(&mut self) {
+        // hide the cursor while rows are written
+        let mut out = String::from("\x1b[?25l");
+        let blank = (" ".to_string(), Style::default());
+
+        for y in 0..self.grid.cells.len() {
+            if self.shown.get(y) == Some(&self.grid.cells[y]) {
+                continue;
+            }
+            self.move_to_row(&mut out, y);
+
+            // write up to the last cell that is not blank, clearing the rest of the row
+            let row = &self.grid.cells[y];
+            let end = row
+                .iter()
+                .rposition(|cell| *cell != blank)
+                .map_or(0, |end| end + 1);
+            let mut style = Style::default();
+            out.push_str("\x1b[0m");
+            for (cell, cell_style) in &row[..end] {
+                if *cell_style != style {
+                    out.push_str(&sgr(*cell_style));
+                    style = *cell_style;
+                }
+                out.push_str(cell);
+            }
+            out.push_str("\x1b[0m\x1b[K");
+        }
+        self.shown = self.grid.cells.clone();
+
+        let (y, x) = self.grid.cursor;
+        let last = self.grid.cells.len().saturating_sub(1);
+        self.move_to_row(&mut out, (y.max(0) as usize).min(last));
+        if x > 0 {
+            out.push_str(&format!("\x1b[{}C", x));
+        }
+        if self.cursor_visible {
+            out.push_str("\x1b[?25h");
+        }
+        let _ = self.write(&out);
+    }
+
+    fn read_key(&mut self) -> Option<Input> {
+        if let Ok(size) = terminal_size(self.tty.as_raw_fd()) {
+            if size != self.terminal_size {
+                // only the rows reserved can be drawn to, (all cleared as the terminal may have
+                // reflowed them)
+                self.terminal_size = size;
+                let rows = self.height.rows(size.0).min(self.reserved);
+                self.grid.resize(rows, size.1);
+                let mut out = String::new();
+                self.move_to_row(&mut out, 0);
+                out.push_str("\x1b[0m\x1b[J");
+                let _ = self.write(&out);
+                self.shown.clear();
+                self.top = self.cursor_position().map(|(row, _)| row);
+                return Some(Input::KeyResize);
+            }
+        }
+
+        let mut decoded = decode_key(&self.unread);
+        if decoded.2 == 0 {
+            // nothing left to decode, or only the start of a character split across reads
+            let bytes = self.read_bytes();
+            self.unread.extend(bytes);
+            decoded = decode_key(&self.unread);
+        }
+        let (input, mouse, length) = decoded;
+        self.unread.drain(..length);
+
+        // clicks are reported at terminal rows, make them relative to the first row drawn to
+        self.mouse = match (mouse, self.top) {
+            | (Some(Mouse::Click { y, x }), Some(top)) => {
+                Some(Mouse::Click { y: y - top, x })
+            }
+            | (Some(Mouse::Click { .. }), None) => None,
+            | (mouse, _) => mouse,
+        };
+        input
+    }
+
+    fn read_mouse(&mut self) -> Option<Mouse> {
+        self.mouse.take()
+    }
+}
+
+/// Return the size of the terminal open as fd, as `(rows, columns)`.
+fn terminal_size(fd: i32) -> io::Result<(usize, usize)> {
+    let mut size: libc::winsize = unsafe { mem::zeroed() };
+    if unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok((size.ws_row as usize, size.ws_col as usize))
+}
+
+/// Return the escape sequence drawing text in style, (from the terminal's own colours).
+fn sgr(style: Style) -> String {
+    let mut codes = vec![String::from("0")];
+    for (set, code) in
+        [(style.bold, "1"), (style.dim, "2"), (style.underline, "4")]
+    {
+        if set == Some(true) {
+            codes.push(code.to_string());
+        }
+    }
+    for (color, base) in [(style.fg, 30), (style.bg, 40)] {
+        match color.map(|color| color.number()) {
+            | None | Some(-1) => {}
+            | Some(number @ 0..=7) => codes.push((base + number).to_string()),
+            | Some(number) => codes.push(format!("{};5;{}", base + 8, number)),
+        }
+    }
+    format!("\x1b[{}m", codes.join(";"))
+}
@@ -0,0 +1,201 @@
+use crate::utils::text::{grapheme_count, grapheme_index, grapheme_offset};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Editing operation on the content of an input widget.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EditAction {
+    Insert(char),
+    Left,
+    Right,
+    Home,
+    End,
+    WordLeft,
+    WordRight,
+    DeleteBackward,
+    DeleteForward,
+    DeleteWordBackward,
+    KillToStart,
+    KillToEnd,
+    Yank,
+}
+
+/// Single line editor with readline style editing, (the content of one input widget at a time).
+///
+/// The cursor counts graphemes into the content. Text removed by the word and kill operations is kept
+/// for `Yank` to insert again, (also into another input after `load`).
+#[derive(Debug, Default, Clone)]
+pub struct LineEditor {
+    content: String,
+    cursor: usize,
+    killed: String,
+}
+
+impl LineEditor {
+    /// Create an editor for the given content with the cursor at its end.
+    pub fn new(content: &str) -> LineEditor {
+        let mut editor = LineEditor::default();
+        editor.load(content);
+        editor
+    }
+
+    /// Start editing other content with the cursor at its end, keeping the killed text.
+    pub fn load(&mut self, content: &str) {
+        self.content = content.to_string();
+        self.cursor = grapheme_count(content);
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// Return the position of the cursor, (in graphemes).
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Move the cursor to the given grapheme, (or the end of the content).
+    pub fn set_cursor(&mut self, position: usize) {
+        self.cursor = position.min(grapheme_count(&self.content));
+    }
+
+    /// Return the content before the cursor.
+    pub fn before_cursor(&self) -> &str {
+        &self.content[..self.offset(self.cursor)]
+    }
+
+    /// Apply an editing operation, returning `true` if it changed the content.
+    pub fn apply(&mut self, action: EditAction) -> bool {
+        let length = grapheme_count(&self.content);
+
+        match action {
+            | EditAction::Insert(c) => {
+                // a combining character joins the grapheme before it, leaving the cursor where it is
+                let offset = self.offset(self.cursor);
+                self.content.insert(offset, c);
+                self.cursor =
+                    grapheme_index(&self.content, offset + c.len_utf8());
+                true
+            }
+            | EditAction::Left => {
+                self.cursor = self.cursor.saturating_sub(1);
+                false
+            }
+            | EditAction::Right => {
+                self.cursor = (self.cursor + 1).min(length);
+                false
+            }
+            | EditAction::Home => {
+                self.cursor = 0;
+                false
+            }
+            | EditAction::End => {
+                self.cursor = length;
+                false
+            }
+            | EditAction::WordLeft => {
+                self.cursor = self.word_start(self.cursor);
+                false
+            }
+            | EditAction::WordRight => {
+                self.cursor = self.word_end(self.cursor);
+                false
+            }
+            | EditAction::DeleteBackward => {
+                if self.cursor == 0 {
+                    return false;
+                }
+                self.cursor -= 1;
+                self.remove(self.cursor, self.cursor + 1);
+                true
+            }
+            | EditAction::DeleteForward => {
+                if self.cursor == length {
+                    return false;
+                }
+                self.remove(self.cursor, self.cursor + 1);
+                true
+            }
+            | EditAction::DeleteWordBackward => {
+                // back to the previous whitespace, (unix-word-rubout)
+                let graphemes: Vec<&str> =
+                    self.content.graphemes(true).collect();
+                let mut start = self.cursor;
+                while start > 0 && is_space(graphemes[start - 1]) {
+                    start -= 1;
+                }
+                while start > 0 && !is_space(graphemes[start - 1]) {
+                    start -= 1;
+                }
+                self.kill(start, self.cursor)
+            }
+            | EditAction::KillToStart => self.kill(0, self.cursor),
+            | EditAction::KillToEnd => self.kill(self.cursor, length),
+            | EditAction::Yank => {
+                let offset = self.offset(self.cursor);
+                self.content.insert_str(offset, &self.killed);
+                self.cursor =
+                    grapheme_index(&self.content, offset + self.killed.len());
+                !self.killed.is_empty()
+            }
+        }
+    }
+
+    /// Return the byte offset of the grapheme at the given position.
+    fn offset(&self, position: usize) -> usize {
+        grapheme_offset(&self.content, position)
+    }
+
+    /// Remove the graphemes from start up to end, returning them.
+    fn remove(&mut self, start: usize, end: usize) -> String {
+        let range = self.offset(start)..self.offset(end);
+        self.content.drain(range).collect()
+    }
+
+    /// Remove the graphemes from start up to end into the killed text and move the cursor to start.
+    ///
+    /// Returns `true` if anything was removed, (killing nothing keeps the killed text).
+    fn kill(&mut self, start: usize, end: usize) -> bool {
+        self.cursor = start;
+        if start == end {
+            return false;
+        }
+        self.killed = self.remove(start, end);
+        true
+    }
+
+    /// Return the start of the word before position, (words are runs of alphanumeric graphemes).
+    fn word_start(&self, position: usize) -> usize {
+        let graphemes: Vec<&str> = self.content.graphemes(true).collect();
+        let mut start = position;
+        while start > 0 && !is_word(graphemes[start - 1]) {
+            start -= 1;
+        }
+        while start > 0 && is_word(graphemes[start - 1]) {
+            start -= 1;
+        }
+        start
+    }
+
+    /// Return the end of the word after position, see `word_start`.
+    fn word_end(&self, position: usize) -> usize {
+        let graphemes: Vec<&str> = self.content.graphemes(true).collect();
+        let mut end = position;
+        while end < graphemes.len() && !is_word(graphemes[end]) {
+            end += 1;
+        }
+        while end < graphemes.len() && is_word(graphemes[end]) {
+            end += 1;
+        }
+        end
+    }
+}
+
+/// Return `true` if the grapheme is part of a word.
+fn is_word(grapheme: &str) -> bool {
+    grapheme.chars().any(char::is_alphanumeric)
+}
+
+/// Return `true` if the grapheme is whitespace.
+fn is_space(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
@@ -0,0 +1,337 @@
+use crate::interface::editor::EditAction;
+use pancurses::Input;
+use std::collections::HashMap;
+use std::fs;
+use std::str::FromStr;
+
+/// Key event, (a pancurses `Input`, with alt held or not).
+///
+/// Keys are written as a single character, (`j`, `G`, `/`), a name, (`enter`, `tab`, `esc`, `space`,
+/// `backspace`, `delete`, `up`, `down`, `left`, `right`, `home`, `end`, `pgup`, `pgdn`, `btab`), or either
+/// with a `ctrl-` or `alt-` prefix, e.g. `ctrl-n` or `alt-b`. Some control keys are the same key as a
+/// named one, (ctrl-i is tab, ctrl-j and ctrl-m are enter and ctrl-h is backspace).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Key {
+    pub input: Input,
+    pub alt: bool,
+}
+
+impl Key {
+    /// Return the key of an input event, (the different codes terminals send for enter and
+    /// backspace are read as the same key).
+    pub fn new(input: Input, alt: bool) -> Key {
+        let input = match input {
+            | Input::KeyEnter | Input::Character('\r') => {
+                Input::Character('\n')
+            }
+            | Input::Character('\u{08}') | Input::Character('\u{7f}') => {
+                Input::KeyBackspace
+            }
+            | input => input,
+        };
+        Key { input, alt }
+    }
+
+    /// Return the character typed by the key, (`None` for control characters and alt combinations).
+    pub fn printable(&self) -> Option<char> {
+        match self.input {
+            | Input::Character(c) if !self.alt && !c.is_control() => Some(c),
+            | _ => None,
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (alt, name) = match s.strip_prefix("alt-") {
+            | Some(name) if !name.is_empty() => (true, name),
+            | _ => (false, s),
+        };
+
+        let mut chars = name.chars();
+        let input = match (chars.next(), chars.next()) {
+            | (Some(c), None) => Input::Character(c),
+            | _ => match name.to_lowercase().as_str() {
+                | "enter" => Input::Character('\n'),
+                | "tab" => Input::Character('\t'),
+                | "esc" => Input::Character('\u{1b}'),
+                | "space" => Input::Character(' '),
+                | "backspace" => Input::KeyBackspace,
+                | "delete" => Input::KeyDC,
+                | "up" => Input::KeyUp,
+                | "down" => Input::KeyDown,
+                | "left" => Input::KeyLeft,
+                | "right" => Input::KeyRight,
+                | "home" => Input::KeyHome,
+                | "end" => Input::KeyEnd,
+                | "pgup" => Input::KeyPPage,
+                | "pgdn" => Input::KeyNPage,
+                | "btab" => Input::KeySTab,
+                | lower => {
+                    // control characters are the letter's code with the upper bits cleared
+                    let letter = lower.strip_prefix("ctrl-").and_then(|rest| {
+                        let mut chars = rest.chars();
+                        match (chars.next(), chars.next()) {
+                            | (Some(c), None) if c.is_ascii_lowercase() => {
+                                Some(c)
+                            }
+                            | _ => None,
+                        }
+                    });
+                    match letter {
+                        | Some(c) => Input::Character((c as u8 & 0x1f) as char),
+                        | None => return Err(()),
+                    }
+                }
+            },
+        };
+
+        Ok(Key::new(input, alt))
+    }
+}
+
+/// What a key binding does.
+///
+/// Editing actions only apply on an input widget. Unbound printable keys are typed into an input
+/// widget, and so are bound ones, (a binding of a printable key only applies on other rows).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+    Select,
+    Toggle,
+    ToggleMark,
+    FocusFilter,
+    Quit,
+    Edit(EditAction),
+}
+
+impl FromStr for Action {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            | "up" => Ok(Action::Up),
+            | "down" => Ok(Action::Down),
+            | "left" => Ok(Action::Left),
+            | "right" => Ok(Action::Right),
+            | "page-up" => Ok(Action::PageUp),
+            | "page-down" => Ok(Action::PageDown),
+            | "top" => Ok(Action::Top),
+            | "bottom" => Ok(Action::Bottom),
+            | "select" => Ok(Action::Select),
+            | "toggle" => Ok(Action::Toggle),
+            | "toggle-mark" => Ok(Action::ToggleMark),
+            | "focus-filter" => Ok(Action::FocusFilter),
+            | "quit" => Ok(Action::Quit),
+            | "beginning-of-line" => Ok(Action::Edit(EditAction::Home)),
+            | "end-of-line" => Ok(Action::Edit(EditAction::End)),
+            | "backward-word" => Ok(Action::Edit(EditAction::WordLeft)),
+            | "forward-word" => Ok(Action::Edit(EditAction::WordRight)),
+            | "backward-delete-char" => {
+                Ok(Action::Edit(EditAction::DeleteBackward))
+            }
+            | "delete-char" => Ok(Action::Edit(EditAction::DeleteForward)),
+            | "backward-kill-word" => {
+                Ok(Action::Edit(EditAction::DeleteWordBackward))
+            }
+            | "unix-line-discard" => Ok(Action::Edit(EditAction::KillToStart)),
+            | "kill-line" => Ok(Action::Edit(EditAction::KillToEnd)),
+            | "yank" => Ok(Action::Edit(EditAction::Yank)),
+            | _ => Err(()),
+        }
+    }
+}
+
+/// Result of looking up the keys pressed so far in a keymap.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Lookup {
+    Action(Action),
+    Prefix,
+    Unbound,
+}
+
+/// Bindings of key sequences, (usually single keys, like `gg` in the vim preset), to actions.
+///
+/// Built from a preset, (`default`, `vim` or `emacs`), and bindings layered on top of it, from a
+/// keymap file or the command line. A sequence that is bound itself is never read as the start of a
+/// longer one.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Keymap {
+    bindings: HashMap<Vec<Key>, Action>,
+}
+
+impl Default for Keymap {
+    /// Arrow and page keys to move, enter to select, space to toggle, tab to quit and readline editing
+    /// keys, (home and end jump to the first/last row outside an input).
+    fn default() -> Self {
+        let mut keymap = Keymap {
+            bindings: HashMap::new(),
+        };
+        keymap.bind_all(&[
+            ("up", "up"),
+            ("down", "down"),
+            ("left", "left"),
+            ("right", "right"),
+            ("pgup", "page-up"),
+            ("pgdn", "page-down"),
+            ("enter", "select"),
+            ("space", "toggle"),
+            ("tab", "quit"),
+            ("btab", "quit"),
+            ("home", "beginning-of-line"),
+            ("ctrl-a", "beginning-of-line"),
+            ("end", "end-of-line"),
+            ("ctrl-e", "end-of-line"),
+            ("alt-b", "backward-word"),
+            ("alt-f", "forward-word"),
+            ("backspace", "backward-delete-char"),
+            ("delete", "delete-char"),
+            ("ctrl-w", "backward-kill-word"),
+            ("ctrl-u", "unix-line-discard"),
+            ("ctrl-k", "kill-line"),
+            ("ctrl-y", "yank"),
+        ]);
+        keymap
+    }
+}
+
+impl Keymap {
+    /// Return the named preset, the default keymap with its own bindings on top.
+    ///
+    /// - **default**: see `Keymap::default`
+    /// - **vim**: `j`/`k` move, `h`/`l` cycle a select, ctrl-f/ctrl-b page, `gg`/`G` jump to the first/last
+    ///   row, `/` focuses the filter, `q` and esc quit
+    /// - **emacs**: ctrl-n/ctrl-p move, ctrl-v/alt-v page, alt-</alt-> jump to the first/last row, ctrl-g
+    ///   quits
+    pub fn preset(name: &str) -> Option<Keymap> {
+        let mut keymap = Keymap::default();
+        match name {
+            | "default" => {}
+            | "vim" => keymap.bind_all(&[
+                ("j", "down"),
+                ("k", "up"),
+                ("h", "left"),
+                ("l", "right"),
+                ("g g", "top"),
+                ("G", "bottom"),
+                ("ctrl-b", "page-up"),
+                ("ctrl-f", "page-down"),
+                ("/", "focus-filter"),
+                ("q", "quit"),
+                ("esc", "quit"),
+            ]),
+            | "emacs" => keymap.bind_all(&[
+                ("ctrl-n", "down"),
+                ("ctrl-p", "up"),
+                ("ctrl-b", "left"),
+                ("ctrl-f", "right"),
+                ("alt-v", "page-up"),
+                ("ctrl-v", "page-down"),
+                ("alt-<", "top"),
+                ("alt->", "bottom"),
+                ("ctrl-g", "quit"),
+            ]),
+            | _ => return None,
+        }
+        Some(keymap)
+    }
+
+    /// Bind a key sequence to an action, replacing any binding it had.
+    pub fn bind(&mut self, keys: Vec<Key>, action: Action) {
+        self.bindings.insert(keys, action);
+    }
+
+    /// Parse and add a binding written as `keys = action`, (keys separated by whitespace).
+    pub fn bind_str(&mut self, binding: &str) -> Result<(), String> {
+        let (keys, action) = binding.split_once('=').ok_or_else(|| {
+            format!("expected keys = action in '{}'", binding)
+        })?;
+
+        let keys = keys
+            .split_whitespace()
+            .map(|key| {
+                key.parse()
+                    .map_err(|_| format!("unknown key '{}'", key.trim()))
+            })
+            .collect::<Result<Vec<Key>, String>>()?;
+        if keys.is_empty() {
+            return Err(format!("missing keys in '{}'", binding));
+        }
+        let action = action
+            .trim()
+            .parse()
+            .map_err(|_| format!("unknown action '{}'", action.trim()))?;
+
+        self.bind(keys, action);
+        Ok(())
+    }
+
+    /// Add the bindings of a keymap file, one `keys = action` per line.
+    ///
+    /// Blank lines and lines starting with `#` are skipped. Returns the first invalid line and why.
+    pub fn bind_lines(&mut self, lines: &str) -> Result<(), String> {
+        for (number, line) in lines.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            self.bind_str(line)
+                .map_err(|error| format!("line {}: {}", number + 1, error))?;
+        }
+        Ok(())
+    }
+
+    /// Layer a preset, (by name), or the bindings of a keymap file, (by path), on top of the keymap.
+    pub fn load(&mut self, preset_or_path: &str) -> Result<(), String> {
+        match Keymap::preset(preset_or_path) {
+            | Some(preset) => {
+                self.bindings.extend(preset.bindings);
+                Ok(())
+            }
+            | None => {
+                let lines =
+                    fs::read_to_string(preset_or_path).map_err(|error| {
+                        format!("{}: {}", preset_or_path, error)
+                    })?;
+                self.bind_lines(&lines)
+                    .map_err(|error| format!("{}: {}", preset_or_path, error))
+            }
+        }
+    }
+
+    /// Look up the keys pressed so far.
+    pub fn lookup(&self, keys: &[Key]) -> Lookup {
+        if let Some(action) = self.bindings.get(keys) {
+            Lookup::Action(*action)
+        } else if self
+            .bindings
+            .keys()
+            .any(|bound| bound.len() > keys.len() && bound.starts_with(keys))
+        {
+            Lookup::Prefix
+        } else {
+            Lookup::Unbound
+        }
+    }
+
+    /// Bind a list of `(keys, action)` pairs written out, (only used for the built-in keymaps).
+    fn bind_all(&mut self, bindings: &[(&str, &str)]) {
+        for (keys, action) in bindings {
+            let keys = keys
+                .split_whitespace()
+                .map(|key| key.parse().expect("built-in key is known"))
+                .collect();
+            let action = action.parse().expect("built-in action is known");
+            self.bind(keys, action);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::fs;
+use std::str::FromStr;
+
+/// Terminal colour, one of the 8 named colours, a 256 colour palette index, (`0` to `255`), or the
+/// terminal's own colour, (`default`).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Color {
+    Default,
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Indexed(u8),
+}
+
+impl Color {
+    /// Return the curses number of the colour, (-1 for the terminal's own colour).
+    pub fn number(&self) -> i16 {
+        match self {
+            | Color::Default => -1,
+            | Color::Black => 0,
+            | Color::Red => 1,
+            | Color::Green => 2,
+            | Color::Yellow => 3,
+            | Color::Blue => 4,
+            | Color::Magenta => 5,
+            | Color::Cyan => 6,
+            | Color::White => 7,
+            | Color::Indexed(index) => *index as i16,
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            | "default" => Ok(Color::Default),
+            | "black" => Ok(Color::Black),
+            | "red" => Ok(Color::Red),
+            | "green" => Ok(Color::Green),
+            | "yellow" => Ok(Color::Yellow),
+            | "blue" => Ok(Color::Blue),
+            | "magenta" => Ok(Color::Magenta),
+            | "cyan" => Ok(Color::Cyan),
+            | "white" => Ok(Color::White),
+            | index => index.parse().map(Color::Indexed).map_err(|_| ()),
+        }
+    }
+}
+
+/// Colours and attributes text is drawn in.
+///
+/// Every field is optional, an unset one is taken from the style drawn under it, (see `over`), and
+/// is the terminal's own colour, (or no attribute), if no style sets it.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub bold: Option<bool>,
+    pub dim: Option<bool>,
+    pub underline: Option<bool>,
+}
+
+impl Style {
+    /// Return the style drawn over base, (the fields it does not set are taken from base).
+    pub fn over(self, base: Style) -> Style {
+        Style {
+            fg: self.fg.or(base.fg),
+            bg: self.bg.or(base.bg),
+            bold: self.bold.or(base.bold),
+            dim: self.dim.or(base.dim),
+            underline: self.underline.or(base.underline),
+        }
+    }
+
+    /// Set the field named by an attribute, (`fg`, `bg`, `bold`, `dim` or `underline`), from its
+    /// written value.
+    ///
+    /// Colours are parsed as a `Color`, the other attributes as `true` or `false`.
+    pub fn set(&mut self, attribute: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("invalid attribute '{}={}'", attribute, value);
+        match attribute {
+            | "fg" => self.fg = Some(value.parse().map_err(|_| invalid())?),
+            | "bg" => self.bg = Some(value.parse().map_err(|_| invalid())?),
+            | "bold" => self.bold = Some(value.parse().map_err(|_| invalid())?),
+            | "dim" => self.dim = Some(value.parse().map_err(|_| invalid())?),
+            | "underline" => {
+                self.underline = Some(value.parse().map_err(|_| invalid())?)
+            }
+            | _ => return Err(format!("unknown attribute '{}'", attribute)),
+        }
+        Ok(())
+    }
+
+    /// Return `true` if the style sets either colour.
+    pub fn has_color(&self) -> bool {
+        self.fg.is_some() || self.bg.is_some()
+    }
+}
+
+/// Styles of each part of the ui.
+///
+/// - **normal**: widgets, (a TEXT or INPUT widget's own style is drawn over it)
+/// - **selected**: the row of the cursor, (drawn over normal, unless it is an input)
+/// - **label**: labels of inputs and selects
+/// - **placeholder**: placeholder of an empty input
+/// - **highlight**: characters matched by a filter
+/// - **border**: the left margin, (the selection carrot and marks)
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Theme {
+    pub normal: Style,
+    pub selected: Style,
+    pub label: Style,
+    pub placeholder: Style,
+    pub highlight: Style,
+    pub border: Style,
+}
+
+impl Default for Theme {
+    /// Terminal colours, with matches bold and placeholders dim.
+    fn default() -> Self {
+        Theme {
+            normal: Style::default(),
+            selected: Style::default(),
+            label: Style::default(),
+            placeholder: Style {
+                dim: Some(true),
+                ..Style::default()
+            },
+            highlight: Style {
+                bold: Some(true),
+                ..Style::default()
+            },
+            border: Style::default(),
+        }
+    }
+}
+
+impl Theme {
+    /// Parse and set the style of a part written as `part = attribute=value ...`, (attributes
+    /// separated by whitespace, unset ones keep their current value).
+    pub fn set_str(&mut self, line: &str) -> Result<(), String> {
+        let (part, attributes) = line
+            .split_once('=')
+            .ok_or_else(|| format!("expected part = style in '{}'", line))?;
+
+        let style = match part.trim() {
+            | "normal" => &mut self.normal,
+            | "selected" => &mut self.selected,
+            | "label" => &mut self.label,
+            | "placeholder" => &mut self.placeholder,
+            | "highlight" => &mut self.highlight,
+            | "border" => &mut self.border,
+            | part => return Err(format!("unknown part '{}'", part)),
+        };
+
+        for attribute in attributes.split_whitespace() {
+            let (name, value) = attribute.split_once('=').ok_or_else(|| {
+                format!("expected attribute=value in '{}'", attribute)
+            })?;
+            style.set(name, value)?;
+        }
+        Ok(())
+    }
+
+    /// Set the styles of a theme file, one `part = attribute=value ...` per line.
+    ///
+    /// Blank lines and lines starting with `#` are skipped. Returns the first invalid line and why.
+    pub fn set_lines(&mut self, lines: &str) -> Result<(), String> {
+        for (number, line) in lines.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            self.set_str(line)
+                .map_err(|error| format!("line {}: {}", number + 1, error))?;
+        }
+        Ok(())
+    }
+
+    /// Set the styles of the theme file at path on top of the theme.
+    pub fn load(&mut self, path: &str) -> Result<(), String> {
+        let lines = fs::read_to_string(path)
+            .map_err(|error| format!("{}: {}", path, error))?;
+        self.set_lines(&lines)
+            .map_err(|error| format!("{}: {}", path, error))
+    }
+}
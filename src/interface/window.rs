@@ -1,9 +1,21 @@
-extern crate pancurses;
-
-use crate::external::widget::{Filter, Widget};
-use crate::utils::filter::{exact_match, fuzzy_match};
+use crate::external::widget::{Case, Filter, Widget};
+use crate::interface::backend::{Backend, Mouse};
+use crate::interface::editor::{EditAction, LineEditor};
+use crate::interface::keymap::{Action, Key, Keymap, Lookup};
+use crate::interface::theme::Theme;
+use crate::utils::filter::{
+    fold, regex_match, Candidate, Glob, Match, Query, TermKind,
+};
 use crate::utils::helpers::{find_widget_by_y, find_widget_by_y_mut};
-use pancurses::*;
+use crate::utils::text::{
+    char_width, display_width, fit_width, grapheme_at_column, grapheme_offset,
+};
+use pancurses::Input;
+use rayon::prelude::*;
+use regex::RegexBuilder;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use unicode_segmentation::UnicodeSegmentation;
 
 #[derive(Debug, PartialEq)]
 pub enum BreakCondition {
@@ -12,88 +24,319 @@ pub enum BreakCondition {
     INPUT,
 }
 
+/// Columns left of every widget, (the selection carrot and mark are drawn in them).
+const LEFT_MARGIN: i32 = 2;
+
+/// Row of the cursor, (its position within an input widget is kept by the line editor), the first
+/// row shown on the terminal and the first grapheme shown of an input on the cursor's row.
+///
+/// Rows count the widgets laid out, (every widget but hidden text widgets), from the top of the model.
 #[derive(Debug)]
 struct Cursor {
-    x: usize,
-    y: usize,
+    row: usize,
+    top: usize,
+    left: usize,
 }
 
-// holds current ncurses window
-static mut WINDOW: Option<Window> = None;
+impl Cursor {
+    /// Return the terminal row the cursor is on.
+    fn y(&self) -> i32 {
+        self.row as i32 - self.top as i32
+    }
 
-/// Return the ncurses window if it exists, or initialize it.
-///
-/// Operation is unsafe for 2 reasons:
-/// * accessing shared mutable state, (this will break if `get_window` is called asynchronously),
-///   which it is not
-/// * foreign function interface, ("I'm responsible for ensuring the safety when interacting with this external C code")
-fn get_window() -> &'static mut Window {
-    unsafe { WINDOW.get_or_insert_with(|| initscr()) }
+    /// Scroll so the cursor's row is one of the given number of terminal rows, with as few rows left
+    /// empty past the last of `rows` as possible.
+    ///
+    /// Returns `true` if the first row shown changed.
+    fn scroll_into_view(&mut self, rows: usize, height: usize) -> bool {
+        let height = height.max(1);
+        let top = self
+            .top
+            .min(rows.saturating_sub(height))
+            .clamp((self.row + 1).saturating_sub(height), self.row);
+        let scrolled = top != self.top;
+        self.top = top;
+        scrolled
+    }
+
+    /// Scroll the content of the input on the cursor's row so the grapheme at position, (the
+    /// editor's cursor), is shown in a field of the given width, (the cursor may sit in the column
+    /// after it).
+    ///
+    /// Returns `true` if the first grapheme shown changed.
+    fn scroll_input(
+        &mut self,
+        content: &str,
+        position: usize,
+        width: usize,
+    ) -> bool {
+        let widths: Vec<usize> =
+            content.graphemes(true).map(display_width).collect();
+        let mut left = self.left.min(position);
+        let mut shown: usize = widths[left..position].iter().sum();
+        while shown > width {
+            shown -= widths[left];
+            left += 1;
+        }
+        let scrolled = left != self.left;
+        self.left = left;
+        scrolled
+    }
 }
 
-// holds vertical offset of aforementioned window
-static mut SCROLL_OFFSET: i32 = 0;
+/// Return the number of columns the content of an input is shown in, its `max_width` or the columns
+/// left on a terminal as wide as given after the label, (and a column for the cursor), if fewer.
+fn field_width(columns: i32, label: &str, max_width: usize) -> usize {
+    let left = columns - LEFT_MARGIN - display_width(label) as i32 - 1;
+    max_width.min(left.max(1) as usize)
+}
 
-/// Return the current scroll offset.
-///
-/// Operation is unsafe because it accesses shared mutable state, (so `get_scroll_offset` must only be called synchronously)
-fn get_scroll_offset() -> &'static i32 {
-    unsafe { &SCROLL_OFFSET }
+/// Return the number of rows the model takes up, (hidden text widgets take none).
+fn row_count(model: &[Widget]) -> usize {
+    model
+        .iter()
+        .filter(|widget| !matches!(widget, Widget::Text { show: false, .. }))
+        .count()
 }
 
-/// Set the scroll offset shared variable to 0.
-fn reset_scroll_offset() {
-    unsafe {
-        SCROLL_OFFSET = 0;
-    }
+/// Return the row the widget with the given id is laid out on, (`None` if it is a hidden text
+/// widget or not in the model).
+fn row_of(model: &[Widget], id: usize) -> Option<usize> {
+    model
+        .iter()
+        .filter(|widget| !matches!(widget, Widget::Text { show: false, .. }))
+        .position(|widget| match widget {
+            | Widget::Input { id: widget_id, .. }
+            | Widget::Text { id: widget_id, .. }
+            | Widget::Checkbox { id: widget_id, .. }
+            | Widget::Select { id: widget_id, .. } => *widget_id == id,
+        })
+}
+
+/// Filtering state kept between keystrokes, so typing only re-checks what it has to.
+#[derive(Debug, Default)]
+struct FilterCache {
+    // prepared content of every text widget, by id, as is and with diacritics removed
+    candidates: HashMap<usize, Candidate>,
+    folded: HashMap<usize, Candidate>,
+    // id of the input last filtered by and its query, (unless filtered by another kind of pattern)
+    last: Option<(usize, Option<Query>)>,
 }
 
-/// Add a positive or negative increment to the shared scroll offset variable.
-fn scroll(inc: i32) {
-    unsafe {
-        SCROLL_OFFSET += inc;
+impl FilterCache {
+    /// Return the content of every text widget prepared for matching, (once per model).
+    fn prepare(
+        &mut self,
+        model: &[Widget],
+        normalize: bool,
+    ) -> &HashMap<usize, Candidate> {
+        let (candidates, prepare): (_, fn(&str) -> Candidate) = if normalize {
+            (&mut self.folded, Candidate::folded)
+        } else {
+            (&mut self.candidates, Candidate::new)
+        };
+
+        if candidates.is_empty() {
+            *candidates = model
+                .par_iter()
+                .filter_map(|widget| match widget {
+                    | Widget::Text { content, id, .. } => {
+                        Some((*id, prepare(content)))
+                    }
+                    | _ => None,
+                })
+                .collect();
+        }
+        candidates
+    }
+
+    /// Return `true` if every widget hidden by the last search is hidden by this one too.
+    ///
+    /// Holds when the same input was last filtered by a query this one narrows, see
+    /// `Query::narrows`. Never holds for fuzzy terms, so fuzzy filters always check every widget.
+    fn narrows(&self, input: usize, query: Option<&Query>) -> bool {
+        match (&self.last, query) {
+            | (Some((last_input, Some(last))), Some(query)) => {
+                *last_input == input && query.narrows(last)
+            }
+            | _ => false,
+        }
     }
 }
 
-/// Given the current window and the ui model, draw all widgets to the window.
+/// Return the text of a select widget, its label followed by the selected option between arrows.
+fn format_select(label: &str, options: &[String], selected: usize) -> String {
+    format!("{}< {} >", label, options[selected])
+}
+
+/// Given the backend and the ui model, draw all widgets to the terminal in the theme's styles.
 ///
-/// Clear the window, and iterate through model, (a vector of widgets) to draw them.
-fn draw(window: &Window, model: &mut Vec<Widget>) {
-    let left_margin: i32 = 2;
-    let mut current_level: i32 = *get_scroll_offset();
+/// Clear the terminal, and iterate through model, (a vector of widgets) to lay them out one per row,
+/// starting with the cursor's first row shown at the top of the terminal. Every widget records the
+/// terminal row it is laid out on, (outside the terminal if it is scrolled out of view, -1 for hidden
+/// text widgets, which take no row). Only the widgets inside the terminal are written.
+fn draw(
+    backend: &mut dyn Backend,
+    model: &mut Vec<Widget>,
+    theme: &Theme,
+    cursor: &Cursor,
+) {
+    let height = backend.size().0;
+    let mut current_level: i32 = -(cursor.top as i32);
 
-    window.clear();
+    backend.clear();
     for widget in model {
         match widget {
-            | Widget::Input {
-                y, content, label, ..
-            } => {
-                // input widget found, write it, its label and content, (both default to "")
-                window.mvprintw(
-                    current_level,
-                    left_margin as i32,
-                    &format!("{}{}", label, content),
+            | Widget::Text { y, show: false, .. } => {
+                // hidden text widget, not on any row
+                *y = -1;
+                continue;
+            }
+            | Widget::Input { y, .. }
+            | Widget::Text { y, .. }
+            | Widget::Checkbox { y, .. }
+            | Widget::Select { y, .. } => *y = current_level,
+        }
+
+        if (0..height).contains(&current_level) {
+            draw_widget(backend, theme, cursor, widget, current_level);
+        }
+        current_level += 1;
+    }
+}
+
+/// Clear row y of the terminal and draw the widget laid out on it again, (if there is one).
+fn draw_row(
+    backend: &mut dyn Backend,
+    model: &[Widget],
+    theme: &Theme,
+    cursor: &Cursor,
+    y: i32,
+) {
+    backend.clear_to_eol(y, 0);
+    if let Some(widget) = find_widget_by_y(model, y) {
+        draw_widget(backend, theme, cursor, widget, y);
+    }
+}
+
+/// Write a single widget to row y of the terminal, its margin, (the selection carrot if it is on the
+/// cursor's row and mark), and its content.
+///
+/// A row is drawn in its widget's style over the theme's normal, (or selected), style, see
+/// `interface::theme::Theme`.
+fn draw_widget(
+    backend: &mut dyn Backend,
+    theme: &Theme,
+    cursor: &Cursor,
+    widget: &Widget,
+    y: i32,
+) {
+    // the cursor is shown on an input instead of the selection carrot
+    let selected = y == cursor.y() && !matches!(widget, Widget::Input { .. });
+    let row_style = if selected {
+        theme.selected.over(theme.normal)
+    } else {
+        theme.normal
+    };
+    if selected {
+        backend.print_styled(y, 0, ">", theme.border.over(theme.normal));
+    }
+
+    match widget {
+        | Widget::Input {
+            max_width,
+            content,
+            label,
+            placeholder,
+            error,
+            style,
+            ..
+        } => {
+            // input widget found, write its label and the part of its content that fits its field,
+            // (or its placeholder while it is empty)
+            let label_style = theme.label.over(row_style);
+            backend.print_styled(y, LEFT_MARGIN, label, label_style);
+            let x = LEFT_MARGIN + display_width(label) as i32;
+            let width = field_width(backend.size().1, label, *max_width);
+            let shown = if content.is_empty() {
+                let shown = fit_width(placeholder, width);
+                let placeholder_style = theme.placeholder.over(row_style);
+                backend.print_styled(y, x, shown, placeholder_style);
+                shown
+            } else {
+                // content scrolled to the first grapheme shown, (only the cursor's row is scrolled)
+                let left = if y == cursor.y() { cursor.left } else { 0 };
+                let start = grapheme_offset(content, left);
+                let shown = fit_width(&content[start..], width);
+                backend.print_styled(y, x, shown, style.over(row_style));
+                shown
+            };
+            if let Some(error) = error {
+                // the content is not a valid pattern, say why after it
+                backend.print_styled(
+                    y,
+                    x + display_width(shown) as i32 + 1,
+                    &format!("({})", error),
+                    row_style,
                 );
-                *y = current_level;
             }
-            | Widget::Text {
-                y, content, show, ..
-            } => {
-                // text widget found, write its content if its show property is `true`
-                if *show {
-                    window.mvprintw(
-                        current_level,
-                        left_margin as i32,
-                        &content,
-                    );
-                    *y = current_level;
-                } else {
-                    *y = -1;
-                    current_level -= 1; // TODO: temp fix for now
+        }
+        | Widget::Text {
+            content,
+            marked,
+            matches,
+            style,
+            ..
+        } => {
+            // text widget found, write its content (and mark)
+            let style = style.over(row_style);
+            backend.print_styled(y, LEFT_MARGIN, content, style);
+            // rewrite the characters matched by the filter highlighted
+            let highlight = theme.highlight.over(style);
+            let mut column = LEFT_MARGIN;
+            for (position, c) in content.chars().enumerate() {
+                if matches.contains(&position) {
+                    backend.print_styled(y, column, &c.to_string(), highlight);
                 }
+                column += char_width(c) as i32;
+            }
+            if *marked {
+                backend.print_styled(
+                    y,
+                    1,
+                    "*",
+                    theme.border.over(theme.normal),
+                );
             }
         }
-        current_level += 1;
+        | Widget::Select {
+            label,
+            options,
+            selected,
+            ..
+        } => {
+            // select widget found, write its label and selected option
+            let text = format_select(label, options, *selected);
+            let (label, option) = text.split_at(label.len());
+            backend.print_styled(
+                y,
+                LEFT_MARGIN,
+                label,
+                theme.label.over(row_style),
+            );
+            let x = LEFT_MARGIN + display_width(label) as i32;
+            backend.print_styled(y, x, option, row_style);
+        }
+        | Widget::Checkbox { label, checked, .. } => {
+            // checkbox widget found, write its mark and label
+            let mark = if *checked { 'x' } else { ' ' };
+            backend.print_styled(
+                y,
+                LEFT_MARGIN,
+                &format!("[{}] {}", mark, label),
+                row_style,
+            );
+        }
     }
 }
 
@@ -104,124 +347,591 @@ fn draw(window: &Window, model: &mut Vec<Widget>) {
 /// Matching works as follows:
 /// - **Filter is off**: set show to `true` for all widgets
 /// - **Filter is Exact**: set show to `true` if the content contains the search string
-/// - **Filter is Fuzzy**: set show to `true` according to Levenshtein distance
-fn filter_widgets(model: &mut Vec<Widget>, filter: Filter, content: &str) {
-    // iterate through all widgets of given model
-    for widget in model {
-        match widget {
-            | Widget::Input { .. } => {}
-            | Widget::Text {
-                content: widget_content,
-                show,
-                ..
-            } => {
-                // widget is text, attempt to filter it
-                let content_lower = content.to_lowercase();
-                let widget_content_lower = widget_content.to_lowercase();
-
-                if filter == Filter::Off || content_lower.is_empty() {
-                    // no filter, make sure all widgets are shown
-                    *show = true;
-                    continue;
-                } else if filter == Filter::Exact {
-                    // filter is exact, hide/show widgets accordingly
-                    if *show
-                        && !exact_match(&widget_content_lower, &content_lower)
-                    {
-                        *show = false;
-                    } else if !*show
-                        && exact_match(&widget_content_lower, &content_lower)
-                    {
-                        *show = true;
-                    }
-                    continue;
-                } else if filter == Filter::Fuzzy {
-                    // filter is fuzzy, hide/show widgets accordingly
-                    if *show
-                        && !fuzzy_match(&content_lower, &widget_content_lower)
-                        && !widget_content_lower.contains(&content_lower)
-                    {
-                        *show = false;
-                    } else if !*show
-                        && (fuzzy_match(&content_lower, &widget_content_lower)
-                            || widget_content_lower.contains(&content_lower))
-                    {
-                        *show = true;
-                    }
-                    continue;
-                }
+/// - **Filter is Fuzzy**: set show to `true` if the search string is a subsequence of the content,
+///   (or is within the Levenshtein distance threshold of it, to forgive typos)
+/// - **Filter is Prefix**: set show to `true` if the content starts with the search string
+/// - **Filter is Regex**: set show to `true` if the regular expression matches in the content
+/// - **Filter is Glob**: set show to `true` if the wildcard pattern matches the whole content
+///
+/// Exact, fuzzy and prefix filters read the search string as an extended search query, (several
+/// terms, negation, anchors and alternatives), matching its plain terms as the filter does, see
+/// `utils::filter::Query`.
+///
+/// Case is ignored as the input's case option says, (smart case only ignores it while the search
+/// string is all lowercase). With `normalize` diacritics are ignored too, see `utils::filter::fold`.
+///
+/// The positions of the matched characters of each text widget are kept for `draw` to highlight.
+/// Text widgets are then reordered amongst themselves, shown ones by descending fuzzy score ahead
+/// of hidden ones, ties keeping their original order. Other widgets keep their place.
+///
+/// Matching runs in parallel on the prepared candidates of the cache, and while the search string of
+/// the given input only grows, (see `FilterCache::narrows`), only widgets still shown are checked.
+///
+/// Returns a short description of the problem if the search string is not a valid pattern,
+/// leaving the widgets as they were.
+fn filter_widgets(
+    model: &mut Vec<Widget>,
+    cache: &mut FilterCache,
+    input: usize,
+    filter: Filter,
+    case: Case,
+    normalize: bool,
+    content: &str,
+) -> Result<(), String> {
+    let case_sensitive = match case {
+        | Case::Smart => content.chars().any(char::is_uppercase),
+        | Case::Sensitive => true,
+        | Case::Insensitive => false,
+    };
+
+    // the search string as candidates are matched, (regexes handle case themselves)
+    let folded = if normalize {
+        fold(content)
+    } else {
+        content.to_string()
+    };
+    let pattern = if case_sensitive {
+        folded.clone()
+    } else {
+        folded.to_lowercase()
+    };
+
+    // compile patterns once for all widgets
+    let regex = match filter {
+        | Filter::Regex => Some(
+            RegexBuilder::new(&folded)
+                .case_insensitive(!case_sensitive)
+                .build()
+                .map_err(|error| match error {
+                    | regex::Error::Syntax(message) => format!(
+                        "invalid regex: {}",
+                        message
+                            .lines()
+                            .last()
+                            .unwrap_or_default()
+                            .trim_start_matches("error: ")
+                    ),
+                    | error => format!("invalid regex: {}", error),
+                })?,
+        ),
+        | _ => None,
+    };
+    let glob = match filter {
+        | Filter::Glob => Some(
+            Glob::new(&pattern)
+                .map_err(|error| format!("invalid glob: {}", error))?,
+        ),
+        | _ => None,
+    };
+    let query = match filter {
+        | Filter::Exact => Some(Query::parse(&pattern, TermKind::Exact)),
+        | Filter::Fuzzy => Some(Query::parse(&pattern, TermKind::Fuzzy)),
+        | Filter::Prefix => Some(Query::parse(&pattern, TermKind::Prefix)),
+        | _ => None,
+    };
+
+    // only widgets still shown can match when the search string narrows the last one
+    let narrowing = cache.narrows(input, query.as_ref());
+    let candidates = cache.prepare(model, normalize);
+
+    let find = |candidate: &Candidate| {
+        let found = match filter {
+            // no filter, make sure all widgets are shown
+            | _ if pattern.is_empty() => Some(Match::default()),
+            | Filter::Off => Some(Match::default()),
+            | Filter::Regex => regex
+                .as_ref()
+                .and_then(|regex| regex_match(candidate.text(), regex)),
+            | Filter::Glob => glob
+                .as_ref()
+                .and_then(|glob| glob.find(candidate.key(case_sensitive))),
+            | Filter::Exact | Filter::Fuzzy | Filter::Prefix => {
+                query.as_ref().and_then(|query| {
+                    query.find_candidate(candidate, case_sensitive)
+                })
+            }
+        };
+        found.map(|found| candidate.unfold(found))
+    };
+
+    // match every text widget across all cores
+    let found: Vec<Option<Option<Match>>> = model
+        .par_iter()
+        .map(|widget| match widget {
+            | Widget::Text { show, id, .. } => Some(if narrowing && !*show {
+                None
+            } else {
+                candidates.get(id).and_then(find)
+            }),
+            | _ => None,
+        })
+        .collect();
+
+    // positions of the text widgets and the rank of each, (lower is drawn first)
+    let mut slots = Vec::new();
+    let mut ranks = Vec::new();
+
+    for (index, (widget, found)) in model.iter_mut().zip(found).enumerate() {
+        if let (
+            Widget::Text {
+                show, matches, id, ..
+            },
+            Some(found),
+        ) = (widget, found)
+        {
+            *show = found.is_some();
+            slots.push(index);
+            ranks.push((Reverse(found.as_ref().map(|found| found.score)), *id));
+            *matches = found.map(|found| found.positions).unwrap_or_default();
+        }
+    }
+
+    cache.last = Some((input, query));
+
+    // put the text widgets back into their positions in order of rank, (unless already in order)
+    let mut order: Vec<usize> = (0..slots.len()).collect();
+    order.par_sort_unstable_by_key(|&i| ranks[i]);
+    if order.iter().enumerate().all(|(k, &i)| k == i) {
+        return Ok(());
+    }
+
+    let mut widgets: Vec<Option<Widget>> =
+        std::mem::take(model).into_iter().map(Some).collect();
+    let mut texts = order.into_iter().map(|i| slots[i]);
+    for index in 0..widgets.len() {
+        let from = if slots.binary_search(&index).is_ok() {
+            texts.next().unwrap_or(index)
+        } else {
+            index
+        };
+        if let Some(widget) = widgets[from].take() {
+            model.push(widget);
+        }
+    }
+
+    Ok(())
+}
+
+/// Filter the model by the content of the input widget at row y.
+///
+/// An invalid pattern is kept on the input widget for `draw` to show next to it.
+fn apply_filter(
+    model: &mut Vec<Widget>,
+    cache: &mut FilterCache,
+    y: i32,
+    filter: Filter,
+    content: &str,
+) {
+    let (input, case, normalize) = match find_widget_by_y(model, y) {
+        | Some(Widget::Input {
+            id,
+            case,
+            normalize,
+            ..
+        }) => (*id, *case, *normalize),
+        | _ => return,
+    };
+    let result =
+        filter_widgets(model, cache, input, filter, case, normalize, content);
+
+    if let Some(Widget::Input { error, .. }) = find_widget_by_y_mut(model, y) {
+        *error = result.err();
+    }
+}
+
+/// Toggle the checkbox at the cursor's row, (if there is one), and redraw it.
+///
+/// Returns `true` if a checkbox was toggled.
+fn toggle_checkbox(
+    backend: &mut dyn Backend,
+    model: &mut [Widget],
+    theme: &Theme,
+    cursor: &Cursor,
+) -> bool {
+    let y = cursor.y();
+    if let Some(Widget::Checkbox { checked, .. }) =
+        find_widget_by_y_mut(model, y)
+    {
+        *checked = !*checked;
+    } else {
+        return false;
+    }
+    draw_row(backend, model, theme, cursor, y);
+    true
+}
+
+/// Move the select widget at the cursor's row, (if there is one), `step` options forward or back and
+/// redraw it.
+///
+/// Options wrap around at either end. Returns `true` if a select widget was changed.
+fn cycle_select(
+    backend: &mut dyn Backend,
+    model: &mut [Widget],
+    theme: &Theme,
+    cursor: &Cursor,
+    step: isize,
+) -> bool {
+    let y = cursor.y();
+    if let Some(Widget::Select {
+        options, selected, ..
+    }) = find_widget_by_y_mut(model, y)
+    {
+        let count = options.len() as isize;
+        *selected = (*selected as isize + step).rem_euclid(count) as usize;
+    } else {
+        return false;
+    }
+    draw_row(backend, model, theme, cursor, y);
+    true
+}
+
+/// Toggle the mark of the text widget at the cursor's row, (if there is one), and redraw it.
+///
+/// Returns `true` if a text widget was marked or unmarked.
+fn toggle_mark(
+    backend: &mut dyn Backend,
+    model: &mut [Widget],
+    theme: &Theme,
+    cursor: &Cursor,
+) -> bool {
+    let y = cursor.y();
+    if let Some(Widget::Text { marked, .. }) = find_widget_by_y_mut(model, y) {
+        *marked = !*marked;
+    } else {
+        return false;
+    }
+    draw_row(backend, model, theme, cursor, y);
+    true
+}
+
+/// Move the terminal cursor to the editor's cursor, (if the cursor is on an input widget), scrolling
+/// the input's content to show it.
+///
+/// The cursor sits after the label and the graphemes of the content shown before it, counted in
+/// columns so wide characters move it 2 cells.
+fn place_cursor(
+    backend: &mut dyn Backend,
+    model: &[Widget],
+    theme: &Theme,
+    cursor: &mut Cursor,
+    editor: &LineEditor,
+) {
+    let y = cursor.y();
+    if let Some(Widget::Input {
+        label, max_width, ..
+    }) = find_widget_by_y(model, y)
+    {
+        let width = field_width(backend.size().1, label, *max_width);
+        if cursor.scroll_input(editor.content(), editor.cursor(), width) {
+            // content scrolled inside its field
+            draw_row(backend, model, theme, cursor, y);
+        }
+
+        // content is drawn after the left margin and the label, from its first grapheme shown
+        let start = grapheme_offset(editor.content(), cursor.left);
+        let column = display_width(label)
+            + display_width(&editor.before_cursor()[start..]);
+        backend.move_cursor(y, LEFT_MARGIN + column as i32);
+    }
+}
+
+/// Apply an editing operation to the input widget at the cursor's row, redrawing it and filtering
+/// the model by its new content.
+///
+/// Content is not let grow wider than the input's `max_width`, (an operation that would is dropped).
+/// Returns `false` if the row is not an input widget, (leaving the key to other widgets).
+fn edit_input(
+    backend: &mut dyn Backend,
+    model: &mut Vec<Widget>,
+    theme: &Theme,
+    cache: &mut FilterCache,
+    editor: &mut LineEditor,
+    cursor: &mut Cursor,
+    action: EditAction,
+) -> bool {
+    let y = cursor.y();
+    let (id, filter) = match find_widget_by_y_mut(model, y) {
+        | Some(Widget::Input {
+            id,
+            max_width,
+            content,
+            filter,
+            ..
+        }) => {
+            let last = editor.clone();
+            if !editor.apply(action) {
+                // only the cursor moved
+                return true;
             }
+            let width = display_width(editor.content());
+            if width > *max_width && width > display_width(content) {
+                // content would grow past its max width, keep it as it was
+                *editor = last;
+                return true;
+            }
+            *content = editor.content().to_string();
+            (*id, filter.clone())
+        }
+        | _ => return false,
+    };
+
+    // redraw the edited row
+    draw_row(backend, model, theme, cursor, y);
+
+    if filter != Filter::Off {
+        // input widget has a filter, apply it
+        apply_filter(model, cache, y, filter, editor.content());
+        // text widgets hidden or reordered above the input move it to another row
+        if let Some(row) = row_of(model, id) {
+            cursor.row = row;
+        }
+        cursor.scroll_into_view(row_count(model), backend.size().0 as usize);
+        draw(backend, model, theme, cursor);
+    }
+    true
+}
+
+/// Move the cursor to the given row, (or the last row if there are fewer), scrolling the model just
+/// enough to show it.
+fn move_to(
+    backend: &mut dyn Backend,
+    model: &mut Vec<Widget>,
+    theme: &Theme,
+    cursor: &mut Cursor,
+    editor: &mut LineEditor,
+    row: usize,
+) {
+    let rows = row_count(model);
+    let row = row.min(rows.saturating_sub(1));
+    if row == cursor.row {
+        return;
+    }
+
+    let last_y = cursor.y();
+    cursor.row = row;
+    cursor.left = 0;
+    if cursor.scroll_into_view(rows, backend.size().0 as usize) {
+        draw(backend, model, theme, cursor);
+    } else {
+        // redraw the row left unselected and the row selected
+        draw_row(backend, model, theme, cursor, last_y);
+        draw_row(backend, model, theme, cursor, cursor.y());
+    }
+
+    if let Some(Widget::Input { content, .. }) =
+        find_widget_by_y(model, cursor.y())
+    {
+        // new row is input, show cursor at end of its content
+        backend.show_cursor(true);
+        editor.load(content);
+    } else {
+        // new row is text, hide cursor, (the selection carrot is drawn instead)
+        backend.show_cursor(false);
+    }
+}
+
+/// Read the next key from the backend, (`None` if there is none yet).
+///
+/// Terminals send alt combinations as escape followed by the key, so an escape followed by a
+/// character is read as that character with alt held. Any other key after an escape is kept in
+/// `unread` for the next read.
+fn read_key(
+    backend: &mut dyn Backend,
+    unread: &mut Option<Input>,
+) -> Option<Key> {
+    let input = unread.take().or_else(|| backend.read_key())?;
+    if input != Input::Character('\u{1b}') {
+        return Some(Key::new(input, false));
+    }
+
+    match backend.read_key() {
+        | Some(Input::Character(c)) => {
+            Some(Key::new(Input::Character(c), true))
+        }
+        | next => {
+            *unread = next;
+            Some(Key::new(input, false))
         }
     }
 }
 
 /// Main ui function to process user input.
 ///
-/// # Update loop
+/// Keys are looked up in the keymap, (see `interface::keymap::Keymap`), a key that starts a bound
+/// sequence waits for the rest of it. On an input widget printable keys are always typed, and
+/// editing actions apply to its content, see `interface::editor::LineEditor`.
+///
+/// # Actions
 ///
-/// - **Escape**: quit program
-/// - **Enter**: select current widget
-/// - **Arrow keys**: move cursor 1 character up/down/left/right
-/// - **Any other charcater**: type given character, (including backspace)
+/// - **Quit**: quit program, (closing the terminal does too)
+/// - **Select**: select current widget, (toggle it if it is a checkbox, cycle it if it is a select)
+/// - **Toggle**: toggle current widget if it is a checkbox, cycle it if it is a select, (or mark it if it is
+///   text and `multi_select` is set)
+/// - **Toggle mark**: mark current widget if it is text and `multi_select` is set
+/// - **Up/down**: move cursor 1 row, **page up/down** a terminal height of rows, **top/bottom** to the
+///   first/last row, (as do the beginning/end-of-line actions outside an input)
+/// - **Left/right**: move cursor 1 character on an input, cycle options on a select
+/// - **Focus filter**: move cursor to the first input with a filter, (or the first input)
+///
+/// A resize of the terminal redraws the model, scrolled as little as needed to keep the cursor's row in
+/// view. With the mouse enabled, (see `interface::backend::Curses::enable_mouse`), a click selects the
+/// row clicked, (or moves to an input and its cursor to the column clicked), and the wheel moves the
+/// cursor up or down.
 fn wait_for_input(
-    window: &Window,
+    backend: &mut dyn Backend,
     model: &mut Vec<Widget>,
+    multi_select: bool,
+    keymap: &Keymap,
+    theme: &Theme,
 ) -> (BreakCondition, usize) {
-    let mut cursor = Cursor { x: 0, y: 0 };
-    reset_scroll_offset();
+    let mut cursor = Cursor {
+        row: 0,
+        top: 0,
+        left: 0,
+    };
+    let mut editor = LineEditor::default();
+    let mut cache = FilterCache::default();
     let mut break_condition: BreakCondition;
-    let mut limit = model.len() - 1;
-    let left_margin: usize = 2;
     let mut current_widget: usize = 0;
-    let height = window.get_max_y() as usize;
-
-    window.mv(cursor.y as i32, cursor.x as i32);
+    let mut pending: Vec<Key> = Vec::new();
+    let mut unread: Option<Input> = None;
 
-    draw(window, model);
+    draw(backend, model, theme, &cursor);
 
-    if let Some(Widget::Input { content, label, .. }) =
-        find_widget_by_y(model, cursor.y as i32)
+    if let Some(Widget::Input { content, .. }) =
+        find_widget_by_y(model, cursor.y())
     {
         // starting widget is input, move cursor to end of its content
-        cursor.x = content.len() + label.len();
+        editor.load(content);
     } else {
         // starting widget is text, hide the cursor
-        curs_set(0);
-        window.mvprintw(0, 0, ">");
+        backend.show_cursor(false);
     }
+    place_cursor(backend, model, theme, &mut cursor, &editor);
 
     // main keyboard input loop
     loop {
-        let ch = window.getch();
-        match ch {
-            | Some(
-                Input::Character('\u{09}')
-                | Input::KeyClose
-                | Input::KeyCancel
-                | Input::KeySTab
-                | Input::KeyCTab,
-            ) => {
-                // tab pressed, exit loop and set program to quit
+        let key = match read_key(backend, &mut unread) {
+            | Some(key) => key,
+            | None => continue,
+        };
+
+        if key.input == Input::KeyResize {
+            // terminal resized, lay the model out again with the cursor's row still in view
+            cursor
+                .scroll_into_view(row_count(model), backend.size().0 as usize);
+            draw(backend, model, theme, &cursor);
+            place_cursor(backend, model, theme, &mut cursor, &editor);
+            backend.refresh();
+            continue;
+        }
+
+        let mouse = match key.input {
+            | Input::KeyMouse => backend.read_mouse(),
+            | _ => None,
+        };
+        if let Some(Mouse::Click { y, x }) = mouse {
+            // move to the row clicked, (an input's cursor to the column clicked)
+            let column = match find_widget_by_y(model, y) {
+                | _ if !(0..backend.size().0).contains(&y) => continue,
+                | Some(Widget::Input { label, .. }) => {
+                    Some(x - LEFT_MARGIN - display_width(label) as i32)
+                }
+                | Some(_) => None,
+                | None => continue,
+            };
+            let row = cursor.top + y as usize;
+            move_to(backend, model, theme, &mut cursor, &mut editor, row);
+            if let Some(column) = column {
+                // the content shown starts from its first grapheme shown
+                let content = editor.content();
+                let shown = &content[grapheme_offset(content, cursor.left)..];
+                let position = cursor.left
+                    + grapheme_at_column(shown, column.max(0) as usize);
+                editor.set_cursor(position);
+            }
+        }
+
+        let y = cursor.y();
+        let height = backend.size().0.max(1) as usize;
+        let on_input =
+            matches!(find_widget_by_y(model, y), Some(Widget::Input { .. }));
+
+        let action = if key.input == Input::KeyMouse {
+            // a click chooses the row clicked unless it is an input, the wheel moves a row
+            match mouse {
+                | Some(Mouse::Click { .. }) if on_input => None,
+                | Some(Mouse::Click { .. }) => Some(Action::Select),
+                | Some(Mouse::ScrollUp) => Some(Action::Up),
+                | Some(Mouse::ScrollDown) => Some(Action::Down),
+                | None => None,
+            }
+        } else {
+            match key.printable() {
+                | Some(c) if on_input && pending.is_empty() => {
+                    Some(Action::Edit(EditAction::Insert(c)))
+                }
+                | _ if matches!(
+                    key.input,
+                    Input::KeyClose | Input::KeyCancel
+                ) =>
+                {
+                    // terminal closed, quit whatever the keymap says
+                    Some(Action::Quit)
+                }
+                | _ => {
+                    pending.push(key);
+                    let mut lookup = keymap.lookup(&pending);
+                    if lookup == Lookup::Unbound && pending.len() > 1 {
+                        // sequence broken off, start a new one from the last key
+                        pending.drain(..pending.len() - 1);
+                        lookup = keymap.lookup(&pending);
+                    }
+                    match lookup {
+                        | Lookup::Action(action) => {
+                            pending.clear();
+                            Some(action)
+                        }
+                        | Lookup::Prefix => None,
+                        | Lookup::Unbound => {
+                            pending.clear();
+                            None
+                        }
+                    }
+                }
+            }
+        };
+
+        match action {
+            | Some(Action::Quit) => {
                 break_condition = BreakCondition::QUIT;
                 break;
             }
-            | Some(Input::KeyEnter) | Some(Input::Character('\n')) => {
+            | Some(Action::Select) => {
+                if toggle_checkbox(backend, model, theme, &cursor)
+                    || cycle_select(backend, model, theme, &cursor, 1)
+                {
+                    // current row is a checkbox or select, changing it does not end the loop
+                    backend.refresh();
+                    continue;
+                }
+
                 // enter/return pressed, prepare program to quit
                 break_condition = BreakCondition::QUIT;
 
                 for widget in model {
                     match widget {
-                        | Widget::Input { y, id, .. }
-                            if *y == (cursor.y as i32) =>
-                        {
+                        | Widget::Input {
+                            y: widget_y, id, ..
+                        } if *widget_y == y => {
                             // selected widget is input, set break condition and selected id
                             current_widget = *id;
                             break_condition = BreakCondition::INPUT;
                             break;
                         }
-                        | Widget::Text { y, id, .. }
-                            if *y == (cursor.y as i32) =>
-                        {
+                        | Widget::Text {
+                            y: widget_y, id, ..
+                        } if *widget_y == y => {
                             // selected widget is text, set break condition and selected id
                             current_widget = *id;
                             break_condition = BreakCondition::SELECTION;
@@ -236,213 +946,171 @@ fn wait_for_input(
                 // conditions have been set, exit loop
                 break;
             }
-            | Some(Input::KeyUp) => {
-                // up arrow pressed, move cursor up
-                if *get_scroll_offset() < 0 && cursor.y == 0 {
-                    // cursor at top of window and widgets exist above cursor, scroll up
-                    scroll(1);
-                    draw(window, model);
-                    cursor.y = 1;
-                    limit += 1;
-                }
-
-                if cursor.y > 0 {
-                    // now move cursor up 1 level
-                    window.mvprintw(cursor.y as i32, 0, " ");
-                    cursor.y -= 1;
-
-                    if let Some(Widget::Input { content, label, .. }) =
-                        find_widget_by_y(model, cursor.y as i32)
-                    {
-                        // new row is input, show cursor at end of its content
-                        curs_set(1);
-                        cursor.x = content.len() + label.len();
-                    } else {
-                        // new row is text, hide cursor and show selection carrot
-                        curs_set(0);
-                        window.mvprintw(cursor.y as i32, 0, ">");
-                    }
-                }
+            | Some(Action::Up) => {
+                let row = cursor.row.saturating_sub(1);
+                move_to(backend, model, theme, &mut cursor, &mut editor, row);
             }
-            | Some(Input::KeyDown) => {
-                // down arrow pressed, move cursor down
-                if cursor.y < limit {
-                    // only move down if more widgets to scroll to
-                    window.mvprintw(cursor.y as i32, 0, " ");
-                    cursor.y += 1;
-
-                    if cursor.y > height - 1 {
-                        // cursor at bottom of window, scroll window
-                        scroll(-1);
-                        draw(window, model);
-                        cursor.y -= 1;
-                        limit -= 1;
-                    }
-
-                    if let Some(Widget::Input { content, label, .. }) =
-                        find_widget_by_y(model, cursor.y as i32)
-                    {
-                        // new row is input, show cursor at end of its content
-                        curs_set(1);
-                        cursor.x = content.len() + label.len();
-                    } else {
-                        // new row is text, hide cursor and show selection carrot
-                        curs_set(0);
-                        window.mvprintw(cursor.y as i32, 0, ">");
-                    }
-                }
+            | Some(Action::Down) => {
+                let row = cursor.row + 1;
+                move_to(backend, model, theme, &mut cursor, &mut editor, row);
             }
-            | Some(Input::KeyLeft) => {
-                // left arrow pressed, move cursor up to label 1 cell if row is input
-                if let Some(Widget::Input { label, .. }) =
-                    find_widget_by_y(model, cursor.y as i32)
-                {
-                    if cursor.x > label.len() {
-                        cursor.x -= 1;
-                    }
-                }
+            | Some(Action::PageUp) => {
+                let row = cursor.row.saturating_sub(height);
+                move_to(backend, model, theme, &mut cursor, &mut editor, row);
             }
-            | Some(Input::KeyRight) => {
-                // right arrow pressed, move cursor up to end to content 1 cell if row is input
-                if let Some(Widget::Input { content, label, .. }) =
-                    find_widget_by_y(model, cursor.y as i32)
-                {
-                    if cursor.x < content.len() + label.len() {
-                        cursor.x += 1;
-                    }
-                }
+            | Some(Action::PageDown) => {
+                let row = cursor.row + height;
+                move_to(backend, model, theme, &mut cursor, &mut editor, row);
             }
-            | Some(Input::KeyBackspace)
-            | Some(Input::KeyDC)
-            | Some(Input::Character('\u{7f}')) => {
-                // backspace/delete pressed
-
-                let mut content;
-                let filter;
-                let label_len;
-
-                if let Some(Widget::Input {
-                    content: ref_content,
-                    filter: ref_filter,
-                    label,
-                    ..
-                }) = find_widget_by_y(model, cursor.y as i32).cloned()
-                {
-                    // current row is input, get its content and filter type
-                    content = ref_content.clone();
-                    filter = ref_filter.clone();
-                    label_len = label.len();
-                } else {
-                    // current row is text, nothing to backspace so skip
-                    continue;
-                }
-
-                if cursor.x > label_len {
-                    // TODO: may need to be label len
-                    // move cursor 1 cell and delete current character
-                    cursor.x -= 1;
-                    window.mv(cursor.y as i32, (cursor.x + left_margin) as i32);
-                    window.delch();
-
-                    if let Some(widget) =
-                        find_widget_by_y_mut(model, cursor.y as i32)
-                    {
-                        if let Widget::Input {
-                            content: widget_content,
-                            ..
-                        } = widget
-                        {
-                            // remove same character from widget's content property
-                            // label_len = label.len();
-                            widget_content.remove(cursor.x - label_len);
+            | Some(Action::Top) => {
+                move_to(backend, model, theme, &mut cursor, &mut editor, 0);
+            }
+            | Some(Action::Edit(EditAction::Home)) if !on_input => {
+                move_to(backend, model, theme, &mut cursor, &mut editor, 0);
+            }
+            | Some(Action::Bottom) => {
+                move_to(
+                    backend,
+                    model,
+                    theme,
+                    &mut cursor,
+                    &mut editor,
+                    usize::MAX,
+                );
+            }
+            | Some(Action::Edit(EditAction::End)) if !on_input => {
+                move_to(
+                    backend,
+                    model,
+                    theme,
+                    &mut cursor,
+                    &mut editor,
+                    usize::MAX,
+                );
+            }
+            | Some(Action::FocusFilter) => {
+                // rows of the input widgets and whether each filters, (every input is laid out)
+                let inputs: Vec<(i32, bool)> = model
+                    .iter()
+                    .filter_map(|widget| match widget {
+                        | Widget::Input { y, filter, .. } => {
+                            Some((*y, *filter != Filter::Off))
                         }
-                    }
-
-                    content.remove(cursor.x - label_len);
+                        | _ => None,
+                    })
+                    .collect();
 
-                    if filter != Filter::Off {
-                        // input widget has a filter, apply it
-                        filter_widgets(model, filter, &content);
-                        draw(window, model);
-                    }
+                // move to the first filtering input, (or any input)
+                let target = inputs
+                    .iter()
+                    .find(|(_, filters)| *filters)
+                    .or(inputs.first());
+                if let Some((target_y, _)) = target {
+                    let row = (cursor.top as i32 + target_y) as usize;
+                    move_to(
+                        backend,
+                        model,
+                        theme,
+                        &mut cursor,
+                        &mut editor,
+                        row,
+                    );
                 }
             }
-            | Some(Input::Character(c)) => {
-                // any other character was typed
-
-                let mut content;
-                let filter;
-                let current_level = cursor.y;
-                let mut label_len = 0;
-
-                if let Some(Widget::Input {
-                    content: ref_content,
-                    filter: ref_filter,
-                    ..
-                }) = find_widget_by_y(model, cursor.y as i32).cloned()
-                {
-                    // current row is input, get its content and filter type
-                    content = ref_content.clone();
-                    filter = ref_filter.clone();
-                } else {
-                    // current row is text, nothing to type so skip
-                    continue;
-                }
-
-                // insert typed character to screen
-                window.mvinsch(
-                    cursor.y as i32,
-                    (cursor.x + left_margin) as i32,
-                    c as chtype,
+            | Some(Action::Left) if !on_input => {
+                // show the previous option of a select
+                cycle_select(backend, model, theme, &cursor, -1);
+            }
+            | Some(Action::Right) if !on_input => {
+                // show the next option of a select
+                cycle_select(backend, model, theme, &cursor, 1);
+            }
+            | Some(Action::Left) => {
+                edit_input(
+                    backend,
+                    model,
+                    theme,
+                    &mut cache,
+                    &mut editor,
+                    &mut cursor,
+                    EditAction::Left,
                 );
-                cursor.x += 1;
-
-                if let Some(widget) =
-                    find_widget_by_y_mut(model, current_level as i32)
+            }
+            | Some(Action::Right) => {
+                edit_input(
+                    backend,
+                    model,
+                    theme,
+                    &mut cache,
+                    &mut editor,
+                    &mut cursor,
+                    EditAction::Right,
+                );
+            }
+            | Some(Action::Edit(edit)) => {
+                // editing keys only apply to an input widget
+                edit_input(
+                    backend,
+                    model,
+                    theme,
+                    &mut cache,
+                    &mut editor,
+                    &mut cursor,
+                    edit,
+                );
+            }
+            | Some(Action::Toggle) => {
+                // change a checkbox, select or mark
+                if toggle_checkbox(backend, model, theme, &cursor)
+                    || cycle_select(backend, model, theme, &cursor, 1)
+                    || (multi_select
+                        && toggle_mark(backend, model, theme, &cursor))
                 {
-                    if let Widget::Input {
-                        content: widget_content,
-                        label,
-                        ..
-                    } = widget
-                    {
-                        // insert same character into widget's content property
-                        label_len = label.len();
-                        widget_content.insert(cursor.x - label_len - 1, c);
-                    }
+                    backend.refresh();
                 }
-
-                content.insert(cursor.x - label_len - 1, c);
-
-                if filter != Filter::Off {
-                    // input widget has a filter, apply it
-                    filter_widgets(model, filter, &content);
-                    draw(window, model);
+            }
+            | Some(Action::ToggleMark) => {
+                if multi_select && toggle_mark(backend, model, theme, &cursor) {
+                    backend.refresh();
                 }
             }
-            | _ => {}
+            | None => {}
         }
 
-        window.mv(cursor.y as i32, (cursor.x + left_margin) as i32);
-        window.refresh();
+        place_cursor(backend, model, theme, &mut cursor, &editor);
+        backend.refresh();
     }
 
-    return (break_condition, current_widget);
+    (break_condition, current_widget)
 }
 
-/// Initialize ui for first time.
-pub fn init(model: &mut Vec<Widget>) -> (BreakCondition, usize) {
-    let window = get_window();
-    window.keypad(true);
-    window.nodelay(true);
-    window.timeout(0);
-    noecho();
-    curs_set(1);
-
-    return wait_for_input(&window, model);
+/// How `init` shows a model.
+///
+/// - **multi_select**: text widgets can be marked
+/// - **keymap**: keys are read through it, see `wait_for_input`
+/// - **theme**: styles widgets are drawn in
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    pub multi_select: bool,
+    pub keymap: Keymap,
+    pub theme: Theme,
 }
 
-/// Tear down ui after program has run to completion.
-pub fn destroy() {
-    endwin();
+/// Show the ui model on the given backend until a widget is chosen or the user quits.
+///
+/// Returns the break condition and the id of the chosen widget.
+pub fn init(
+    backend: &mut dyn Backend,
+    model: &mut Vec<Widget>,
+    options: &Options,
+) -> (BreakCondition, usize) {
+    backend.show_cursor(true);
+
+    wait_for_input(
+        backend,
+        model,
+        options.multi_select,
+        &options.keymap,
+        &options.theme,
+    )
 }
@@ -1,16 +1,22 @@
 pub mod external {
     pub mod exec;
     pub mod model;
+    pub mod syntax;
     pub mod widget;
 }
 pub mod utils {
     pub mod args;
+    pub mod error;
     pub mod filter;
     pub mod helpers;
+    pub mod text;
 }
 pub mod interface {
+    pub mod backend;
+    pub mod editor;
+    pub mod keymap;
+    pub mod theme;
     pub mod window;
 }
-use crate::external::widget::Widget;
 // use crate::interface::window::BreakCondition;
 // use std::process::exit;
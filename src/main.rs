@@ -1,9 +1,40 @@
-use stacklet::external::exec;
+use stacklet::external::exec::{self, Coprocess, Runtime};
 use stacklet::external::widget::Widget;
-use stacklet::interface::window::{destroy, init, BreakCondition};
+use stacklet::interface::backend::{Backend, Curses, Inline};
+use stacklet::interface::theme::Style;
+use stacklet::interface::window::{init, BreakCondition, Options};
 use stacklet::utils::args;
+use stacklet::utils::error::{ParseError, RunError};
+use stacklet::utils::helpers::find_widget_by_id;
 use std::process::exit;
 
+/// Build a model of text widgets describing the given parse errors.
+fn error_model(errors: &[ParseError]) -> Vec<Widget> {
+    let mut lines =
+        vec![String::from("Error: executable wrote invalid stdout")];
+
+    for error in errors {
+        lines.push(error.to_string());
+        lines.push(format!("    {}", error.snippet));
+    }
+
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(id, content)| Widget::Text {
+            y: id as i32,
+            value: content.clone(),
+            content,
+            key: String::new(),
+            show: true,
+            marked: false,
+            matches: Vec::new(),
+            style: Style::default(),
+            id,
+        })
+        .collect()
+}
+
 /// Entry point for the program.
 fn main() {
     // parse commandline arguments
@@ -28,59 +59,201 @@ fn main() {
     }
 
     // initialize runtime variables
-    let mut input = String::from("");
-    let mut input_content = String::from("");
-    let mut selection = String::from("");
-    let mut data = String::from("");
+    let mut runtime = Runtime::default();
+
+    // in coprocess mode the executable is spawned once and kept running, (failing to spawn it is
+    // reported by the first run like any other failure)
+    let mut coprocess = if args.coprocess() {
+        Some(Coprocess::spawn(&exec_path, args.delimiter()))
+    } else {
+        None
+    };
+
+    // run provided executable, (or send an event to it), and parse its stdout
+    let mut run = |runtime: &Runtime| match coprocess.as_mut() {
+        | Some(Ok(coprocess)) => coprocess.request(args.protocol(), runtime),
+        | Some(Err(error)) => Err(error.clone()),
+        | None => exec::run_executable(&exec_path, args.protocol(), runtime),
+    };
+
+    if args.check() {
+        // non-interactive mode, run executable once and report any parse errors
+        match run(&runtime) {
+            | Ok(_) => exit(0),
+            | Err(RunError::Failed(error)) => {
+                eprintln!("{}", error);
+                exit(1);
+            }
+            | Err(RunError::Invalid(errors)) => {
+                for error in errors {
+                    eprintln!("{}\n    {}", error, error.snippet);
+                }
+                exit(1);
+            }
+        }
+    }
+
+    // terminal, initialized when the first screen is shown and ended when dropped, (the whole
+    // screen with ncurses, or rows below the prompt with a height)
+    let mut terminal: Option<Box<dyn Backend>> = None;
+    let open_terminal = || -> Box<dyn Backend> {
+        match args.height() {
+            | Some(height) => {
+                let mut inline = Inline::new(height).unwrap_or_else(|error| {
+                    eprintln!("Error: Cannot open the terminal, {}", error);
+                    exit(1);
+                });
+                if args.mouse() {
+                    inline.enable_mouse();
+                }
+                Box::new(inline)
+            }
+            | None => {
+                let mut curses = Curses::new();
+                if args.mouse() {
+                    curses.enable_mouse();
+                }
+                Box::new(curses)
+            }
+        }
+    };
+
+    // keymap and theme of every screen, (whether text widgets can be marked is set per screen)
+    let mut options = Options {
+        multi_select: false,
+        keymap: args.keymap().clone(),
+        theme: *args.theme(),
+    };
 
     loop {
         // run provided executable and collect ui model (from stdout) and generated data
-        let (mut model, new_data) = exec::run_executable(
-            &exec_path,
-            &input,
-            &input_content,
-            &selection,
-            &data,
-        );
+        let screen = match run(&runtime) {
+            | Ok(screen) => screen,
+            | Err(RunError::Failed(error)) => {
+                // executable failed, restore the terminal before reporting its stderr
+                drop(terminal);
+                eprintln!("{}", error);
+                exit(1);
+            }
+            | Err(RunError::Invalid(errors)) => {
+                // invalid stdout, show the errors until dismissed and quit
+                options.multi_select = false;
+                init(
+                    terminal.get_or_insert_with(open_terminal).as_mut(),
+                    &mut error_model(&errors),
+                    &options,
+                );
+                drop(terminal);
+                exit(1);
+            }
+        };
+
+        let mut model = screen.widgets;
 
         if model.is_empty() {
-            // no stdout, end the app loop
+            // no stdout, end the app loop, (restoring the terminal before running the QUIT() command)
+            drop(terminal);
+            if let Some(command) = screen.quit_command {
+                exec::spawn_detached_child(&command);
+            }
             break;
         }
 
-        if !new_data.is_empty() {
+        if !screen.data.is_empty() {
             // app used DATA() macro
-            data = new_data;
+            runtime.data = screen.data;
         }
 
-        let (break_condition, match_id) = init(&mut model);
+        options.multi_select = args.multi_select() || screen.multi_select;
+        let (break_condition, match_id) = init(
+            terminal.get_or_insert_with(open_terminal).as_mut(),
+            &mut model,
+            &options,
+        );
 
         if break_condition == BreakCondition::SELECTION {
             // user clicked on a text widget, set selection runtime variable for next execution
-            if let Some(Widget::Text { content, .. }) = model.get(match_id) {
-                selection = content.to_string();
-                input = "".to_string();
+            if let Some(Widget::Text { value, key, .. }) =
+                find_widget_by_id(&model, match_id)
+            {
+                runtime.selection = value.to_string();
+                runtime.selection_key = key.to_string();
+                runtime.input = "".to_string();
             }
+
+            // generate a newline deliminated string of all marked text widgets, (or the selected one)
+            let marked = model
+                .iter()
+                .filter_map(|widget| match widget {
+                    | Widget::Text {
+                        value,
+                        marked: true,
+                        ..
+                    } => Some(value.clone()),
+                    | _ => None,
+                })
+                .collect::<Vec<_>>();
+            runtime.selections = if marked.is_empty() {
+                runtime.selection.clone()
+            } else {
+                marked.join("\n")
+            };
         } else if break_condition == BreakCondition::INPUT {
             // user clicked on an input widget, set input runtime variable for next execution
-            if let Some(Widget::Input { content, .. }) = model.get(match_id) {
-                selection = "".to_string();
-                input = content.to_string();
+            if let Some(Widget::Input { content, .. }) =
+                find_widget_by_id(&model, match_id)
+            {
+                runtime.selection = "".to_string();
+                runtime.selection_key = "".to_string();
+                runtime.selections = "".to_string();
+                runtime.input = content.to_string();
             }
         } else if break_condition == BreakCondition::QUIT {
             break;
         }
 
-        // generate a string of all input widgets deliminated by input_content_delimiter for next execution
-        input_content = model
+        // generate a string of all input widgets in the chosen encoding for next execution
+        runtime.inputs = model
             .iter()
             .filter_map(|widget| match widget {
-                | Widget::Input { content, .. } => Some(content.clone()),
+                | Widget::Input { name, content, .. } => {
+                    Some((name.clone(), content.clone()))
+                }
                 | _ => None,
             })
-            .collect::<Vec<_>>()
-            .join(":"); // TODO: pull out to configurable option
-    }
+            .collect();
+        runtime.input_content =
+            exec::encode_inputs(&runtime.inputs, args.encoding());
 
-    destroy();
+        // generate a string of the names of all checked checkboxes in the chosen encoding for next
+        // execution
+        let checked = model
+            .iter()
+            .filter_map(|widget| match widget {
+                | Widget::Checkbox {
+                    name,
+                    checked: true,
+                    ..
+                } => Some(name.clone()),
+                | _ => None,
+            })
+            .collect::<Vec<_>>();
+        runtime.checked = exec::encode_checked(&checked, args.encoding());
+
+        // generate a string of the selected option of all select widgets in the chosen encoding for
+        // next execution
+        let selected = model
+            .iter()
+            .filter_map(|widget| match widget {
+                | Widget::Select {
+                    name,
+                    options,
+                    selected,
+                    ..
+                } => Some((name.clone(), options[*selected].clone())),
+                | _ => None,
+            })
+            .collect::<Vec<_>>();
+        runtime.selected = exec::encode_selected(&selected, args.encoding());
+    }
 }
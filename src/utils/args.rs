@@ -1,10 +1,25 @@
+use crate::external::exec::Encoding;
+use crate::external::model::Protocol;
+use crate::interface::backend::Height;
+use crate::interface::keymap::Keymap;
+use crate::interface::theme::Theme;
 use std::env;
 
 #[derive(Debug)]
 pub struct Args {
     help: bool,
     version: bool,
+    check: bool,
+    coprocess: bool,
+    multi_select: bool,
+    mouse: bool,
+    height: Option<Height>,
     exec_path: Option<String>,
+    protocol: Protocol,
+    delimiter: String,
+    encoding: Encoding,
+    keymap: Keymap,
+    theme: Theme,
 }
 
 impl Args {
@@ -16,9 +31,49 @@ impl Args {
         self.version
     }
 
+    pub fn check(&self) -> bool {
+        self.check
+    }
+
+    pub fn coprocess(&self) -> bool {
+        self.coprocess
+    }
+
+    pub fn multi_select(&self) -> bool {
+        self.multi_select
+    }
+
+    pub fn mouse(&self) -> bool {
+        self.mouse
+    }
+
+    pub fn height(&self) -> Option<Height> {
+        self.height
+    }
+
     pub fn exec_path(&self) -> Option<&String> {
         self.exec_path.as_ref()
     }
+
+    pub fn protocol(&self) -> &Protocol {
+        &self.protocol
+    }
+
+    pub fn delimiter(&self) -> &str {
+        &self.delimiter
+    }
+
+    pub fn encoding(&self) -> &Encoding {
+        &self.encoding
+    }
+
+    pub fn keymap(&self) -> &Keymap {
+        &self.keymap
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
 }
 
 /// Parse command-line arguments and return an instance of Args
@@ -29,7 +84,17 @@ pub fn parse_args() -> Args {
     // initialize default values for optional arguments
     let mut help = false;
     let mut version = false;
+    let mut check = false;
+    let mut coprocess = false;
+    let mut multi_select = false;
+    let mut mouse = false;
+    let mut height: Option<Height> = None;
     let mut exec_path: Option<String> = None;
+    let mut protocol = Protocol::Macro;
+    let mut delimiter = String::from("---");
+    let mut encoding = Encoding::Plain;
+    let mut keymap = Keymap::default();
+    let mut theme = Theme::default();
 
     // iterate through args and match up with known options
     let mut iter = args.iter().peekable();
@@ -37,6 +102,36 @@ pub fn parse_args() -> Args {
         match arg.as_str() {
             | "-h" | "--help" => help = true,
             | "-v" | "--version" => version = true,
+            | "--check" => check = true,
+            | "-c" | "--coprocess" => coprocess = true,
+            | "-m" | "--multi" => multi_select = true,
+            | "--mouse" => mouse = true,
+            | "--height" => {
+                // check if the next argument is a number of rows or a percentage
+                match iter.next().map(|rows| rows.parse()) {
+                    | Some(Ok(rows)) => height = Some(rows),
+                    | Some(Err(_)) => {
+                        println!("Error: Invalid argument for --height");
+                        print_help();
+                        std::process::exit(1);
+                    }
+                    | None => {
+                        println!("Error: Missing argument for --height");
+                        print_help();
+                        std::process::exit(1);
+                    }
+                }
+            }
+            | "-d" | "--delimiter" => {
+                // check if the next argument is available
+                if let Some(line) = iter.next() {
+                    delimiter = line.clone();
+                } else {
+                    println!("Error: Missing argument for -d/--delimiter");
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
             | "-x" | "--exec" => {
                 // check if the next argument is available
                 if let Some(path) = iter.next() {
@@ -47,6 +142,87 @@ pub fn parse_args() -> Args {
                     std::process::exit(1);
                 }
             }
+            | "-p" | "--protocol" => {
+                // check if the next argument is a known protocol
+                match iter.next().map(|name| name.parse()) {
+                    | Some(Ok(name)) => protocol = name,
+                    | Some(Err(_)) => {
+                        println!("Error: Invalid argument for -p/--protocol");
+                        print_help();
+                        std::process::exit(1);
+                    }
+                    | None => {
+                        println!("Error: Missing argument for -p/--protocol");
+                        print_help();
+                        std::process::exit(1);
+                    }
+                }
+            }
+            | "-e" | "--encoding" => {
+                // check if the next argument is a known encoding
+                match iter.next().map(|name| name.parse()) {
+                    | Some(Ok(name)) => encoding = name,
+                    | Some(Err(_)) => {
+                        println!("Error: Invalid argument for -e/--encoding");
+                        print_help();
+                        std::process::exit(1);
+                    }
+                    | None => {
+                        println!("Error: Missing argument for -e/--encoding");
+                        print_help();
+                        std::process::exit(1);
+                    }
+                }
+            }
+            | "-k" | "--keymap" => {
+                // check if the next argument is a preset or a readable keymap file
+                match iter.next().map(|name| keymap.load(name)) {
+                    | Some(Ok(())) => {}
+                    | Some(Err(error)) => {
+                        println!("Error: Invalid keymap, {}", error);
+                        std::process::exit(1);
+                    }
+                    | None => {
+                        println!("Error: Missing argument for -k/--keymap");
+                        print_help();
+                        std::process::exit(1);
+                    }
+                }
+            }
+            | "-b" | "--bind" => {
+                // check if the next argument is a list of valid bindings
+                match iter.next().map(|bindings| {
+                    bindings
+                        .split(',')
+                        .try_for_each(|binding| keymap.bind_str(binding))
+                }) {
+                    | Some(Ok(())) => {}
+                    | Some(Err(error)) => {
+                        println!("Error: Invalid binding, {}", error);
+                        std::process::exit(1);
+                    }
+                    | None => {
+                        println!("Error: Missing argument for -b/--bind");
+                        print_help();
+                        std::process::exit(1);
+                    }
+                }
+            }
+            | "-t" | "--theme" => {
+                // check if the next argument is a readable theme file
+                match iter.next().map(|path| theme.load(path)) {
+                    | Some(Ok(())) => {}
+                    | Some(Err(error)) => {
+                        println!("Error: Invalid theme, {}", error);
+                        std::process::exit(1);
+                    }
+                    | None => {
+                        println!("Error: Missing argument for -t/--theme");
+                        print_help();
+                        std::process::exit(1);
+                    }
+                }
+            }
             | _ => {
                 println!("Error: Unknown argument '{}'", arg);
                 print_help();
@@ -58,16 +234,55 @@ pub fn parse_args() -> Args {
     Args {
         help,
         version,
+        check,
+        coprocess,
+        multi_select,
+        mouse,
+        height,
         exec_path,
+        protocol,
+        delimiter,
+        encoding,
+        keymap,
+        theme,
     }
 }
 
 /// Print the program help message.
 pub fn print_help() {
     println!("Usage:");
-    println!("  -h, --help      Print help message and quit");
-    println!("  -v, --version   Print program version");
-    println!("  -x, --exec      Path to executable (required)");
+    println!("  -h, --help          Print help message and quit");
+    println!("  -v, --version       Print program version");
+    println!("  -x, --exec          Path to executable (required)");
+    println!("  -p, --protocol      Stdout format, macro (default) or json");
+    println!(
+        "      --check         Run executable once and report parse errors"
+    );
+    println!(
+        "  -c, --coprocess     Keep executable running, send events on stdin"
+    );
+    println!(
+        "  -d, --delimiter     Line ending each coprocess model (default ---)"
+    );
+    println!(
+        "  -e, --encoding      INPUT_CONTENT, CHECKED and SELECTED format, plain (default), json, url"
+    );
+    println!("  -m, --multi         Mark several text rows with space on every screen");
+    println!(
+        "      --mouse         Click to choose a row or place the cursor, wheel to move"
+    );
+    println!(
+        "      --height        Draw below the prompt in N rows or N% of the terminal"
+    );
+    println!(
+        "  -k, --keymap        Keymap preset, default, vim or emacs, or keymap file"
+    );
+    println!(
+        "  -b, --bind          Extra bindings, e.g. \"ctrl-j=down,g g=top\""
+    );
+    println!(
+        "  -t, --theme         Theme file, lines like \"selected = fg=black bg=cyan\""
+    );
 }
 
 /// Print the program version.
@@ -0,0 +1,85 @@
+use std::fmt;
+
+/// Reason a line of stdout could not be turned into a widget.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParseErrorKind {
+    ExpectedMacro,
+    Expected(char),
+    UnexpectedEnd,
+    UnterminatedString,
+    InvalidEscape(String),
+    TrailingCharacters,
+    UnknownWidget(String),
+    MissingArgument(String),
+    InvalidValue { param: String, value: String },
+    InvalidJson(String),
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            | ParseErrorKind::ExpectedMacro => write!(f, "expected a macro"),
+            | ParseErrorKind::Expected(c) => write!(f, "expected '{}'", c),
+            | ParseErrorKind::UnexpectedEnd => {
+                write!(f, "unexpected end of line")
+            }
+            | ParseErrorKind::UnterminatedString => {
+                write!(f, "unterminated string")
+            }
+            | ParseErrorKind::InvalidEscape(escape) => {
+                write!(f, "invalid escape sequence '{}'", escape)
+            }
+            | ParseErrorKind::TrailingCharacters => {
+                write!(f, "unexpected characters after macro")
+            }
+            | ParseErrorKind::UnknownWidget(name) => {
+                write!(f, "unknown widget '{}'", name)
+            }
+            | ParseErrorKind::MissingArgument(name) => {
+                write!(f, "missing argument '{}'", name)
+            }
+            | ParseErrorKind::InvalidValue { param, value } => {
+                write!(f, "invalid value '{}' for '{}'", value, param)
+            }
+            | ParseErrorKind::InvalidJson(message) => {
+                write!(f, "invalid json: {}", message)
+            }
+        }
+    }
+}
+
+/// Diagnostic for a line of stdout that could not be parsed.
+///
+/// `line` and `column` are 1-based, `snippet` is the offending line as written by the executable.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+    pub snippet: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.kind
+        )
+    }
+}
+
+/// Reason running the executable did not give a screen.
+#[derive(Debug, PartialEq, Clone)]
+pub enum RunError {
+    /// The executable could not be run or exited unsuccessfully, with what it wrote to stderr.
+    Failed(String),
+    /// The stdout is not a valid model.
+    Invalid(Vec<ParseError>),
+}
+
+impl From<Vec<ParseError>> for RunError {
+    fn from(errors: Vec<ParseError>) -> Self {
+        RunError::Invalid(errors)
+    }
+}
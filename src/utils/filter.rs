@@ -1,6 +1,319 @@
-/// Return `true` if s1 contains s2, and `false` otherwise.
-pub fn exact_match(s1: &str, s2: &str) -> bool {
-    s1.contains(s2)
+use regex::Regex;
+use unicode_normalization::char::{decompose_compatible, is_combining_mark};
+
+/// Result of matching a query against a string.
+///
+/// `positions` are the indices of the matched characters of the string, (in characters, not bytes),
+/// the higher the `score` the better the match.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Match {
+    pub score: i64,
+    pub positions: Vec<usize>,
+}
+
+/// String prepared for matching, its characters and lowercase form computed once up front.
+///
+/// Every character is lowercased to a single character, so positions in the lowercase form are
+/// positions in the text. A folded candidate matches its string with diacritics removed, (see
+/// `fold`), `unfold` maps the positions of a match in it back to the string.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Candidate {
+    text: String,
+    chars: Vec<char>,
+    lower: String,
+    lower_chars: Vec<char>,
+    // character of the string each folded character came from, (`None` if not folded)
+    origins: Option<Vec<usize>>,
+}
+
+impl Candidate {
+    pub fn new(s: &str) -> Candidate {
+        Candidate::prepare(s.chars().collect(), None)
+    }
+
+    /// Prepare s to be matched with its diacritics removed.
+    pub fn folded(s: &str) -> Candidate {
+        let mut chars = Vec::new();
+        let mut origins = Vec::new();
+        for (origin, c) in s.chars().enumerate() {
+            fold_char(c, |folded| {
+                chars.push(folded);
+                origins.push(origin);
+            });
+        }
+
+        Candidate::prepare(chars, Some(origins))
+    }
+
+    fn prepare(chars: Vec<char>, origins: Option<Vec<usize>>) -> Candidate {
+        let lower_chars: Vec<char> =
+            chars.iter().map(|&c| lowercase(c)).collect();
+
+        Candidate {
+            text: chars.iter().collect(),
+            lower: lower_chars.iter().collect(),
+            chars,
+            lower_chars,
+            origins,
+        }
+    }
+
+    /// Return the text matched against, (the string, folded if the candidate is).
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn lower(&self) -> &str {
+        &self.lower
+    }
+
+    /// Return the text as matched in the given case, (lowercase unless case sensitive).
+    pub fn key(&self, case_sensitive: bool) -> &str {
+        if case_sensitive {
+            &self.text
+        } else {
+            &self.lower
+        }
+    }
+
+    /// Map the positions of a match in the text to positions in the string it was prepared from.
+    pub fn unfold(&self, found: Match) -> Match {
+        match &self.origins {
+            | Some(origins) => {
+                let mut positions: Vec<usize> = found
+                    .positions
+                    .iter()
+                    .filter_map(|&position| origins.get(position).copied())
+                    .collect();
+                positions.dedup();
+                Match {
+                    score: found.score,
+                    positions,
+                }
+            }
+            | None => found,
+        }
+    }
+}
+
+/// Return the lowercase form of c, (the first character of it if there are several).
+fn lowercase(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// Return s with its diacritics removed, e.g. "Crème brûlée" becomes "Creme brulee".
+///
+/// Characters are decomposed, (compatibility decomposition, so ligatures like "ﬁ" are split too),
+/// and their combining marks dropped. Letters that do not decompose are spelled with the base
+/// letters they are read as, e.g. "Ærø" becomes "AEro".
+pub fn fold(s: &str) -> String {
+    let mut folded = String::with_capacity(s.len());
+    for c in s.chars() {
+        fold_char(c, |c| folded.push(c));
+    }
+    folded
+}
+
+/// Pass each character of the folded form of c to emit, see `fold`.
+fn fold_char(c: char, mut emit: impl FnMut(char)) {
+    let spelled = match c {
+        | 'Æ' => "AE",
+        | 'æ' => "ae",
+        | 'Œ' => "OE",
+        | 'œ' => "oe",
+        | 'Ø' => "O",
+        | 'ø' => "o",
+        | 'Đ' | 'Ð' => "D",
+        | 'đ' | 'ð' => "d",
+        | 'Ł' => "L",
+        | 'ł' => "l",
+        | 'Þ' => "TH",
+        | 'þ' => "th",
+        | 'ß' => "ss",
+        | 'ı' => "i",
+        | _ => "",
+    };
+
+    if spelled.is_empty() {
+        decompose_compatible(c, |c| {
+            if !is_combining_mark(c) {
+                emit(c);
+            }
+        });
+    } else {
+        spelled.chars().for_each(emit);
+    }
+}
+
+/// Return the match of s2 in s1 if s1 contains s2, and `None` otherwise.
+///
+/// The positions are those of the first occurrence of s2, every exact match scores 0.
+pub fn exact_match(s1: &str, s2: &str) -> Option<Match> {
+    s1.find(s2).map(|start| {
+        let start = s1[..start].chars().count();
+        Match {
+            score: 0,
+            positions: (start..start + s2.chars().count()).collect(),
+        }
+    })
+}
+
+/// Return the match of s2 at the start of s1 if s1 starts with s2, and `None` otherwise.
+pub fn prefix_match(s1: &str, s2: &str) -> Option<Match> {
+    s1.starts_with(s2).then(|| Match {
+        score: 0,
+        positions: (0..s2.chars().count()).collect(),
+    })
+}
+
+/// Return the match of the first occurrence of regex in s1, and `None` if there is none.
+///
+/// The positions are the characters of the matched text, (none for an empty match).
+pub fn regex_match(s1: &str, regex: &Regex) -> Option<Match> {
+    regex.find(s1).map(|found| {
+        let start = s1[..found.start()].chars().count();
+        Match {
+            score: 0,
+            positions: (start..start + found.as_str().chars().count())
+                .collect(),
+        }
+    })
+}
+
+/// Single element of a glob pattern.
+#[derive(Debug, PartialEq, Clone)]
+enum GlobToken {
+    Literal(char),
+    Any,
+    Star,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl GlobToken {
+    /// Return `true` if the token matches the single character c, (a star never does).
+    fn matches(&self, c: char) -> bool {
+        match self {
+            | GlobToken::Literal(literal) => *literal == c,
+            | GlobToken::Any => true,
+            | GlobToken::Star => false,
+            | GlobToken::Class { negated, ranges } => {
+                ranges.iter().any(|(from, to)| (*from..=*to).contains(&c))
+                    != *negated
+            }
+        }
+    }
+}
+
+/// Shell style wildcard pattern, matched against the whole of a string.
+///
+/// `*` matches any run of characters, `?` any single character and `[...]` any character of the
+/// set, (ranges like `a-z` are allowed, `[!...]` negates the set), anything else matches itself.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Glob {
+    tokens: Vec<GlobToken>,
+}
+
+impl Glob {
+    /// Compile a glob pattern, returning a description of the problem if it is invalid.
+    pub fn new(pattern: &str) -> Result<Glob, String> {
+        let mut tokens = Vec::new();
+        let mut chars = pattern.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            let token = match c {
+                | '*' => GlobToken::Star,
+                | '?' => GlobToken::Any,
+                | '[' => {
+                    let negated = chars.next_if_eq(&'!').is_some();
+                    let mut ranges = Vec::new();
+
+                    // a ']' straight after the opening bracket is part of the set
+                    let mut first = true;
+                    loop {
+                        match chars.next() {
+                            | None => {
+                                return Err(String::from(
+                                    "unterminated character class",
+                                ))
+                            }
+                            | Some(']') if !first => break,
+                            | Some(from) => {
+                                let to = if chars.peek() == Some(&'-') {
+                                    let mut lookahead = chars.clone();
+                                    lookahead.next();
+                                    match lookahead.next() {
+                                        | Some(to) if to != ']' => {
+                                            chars = lookahead;
+                                            to
+                                        }
+                                        | _ => from,
+                                    }
+                                } else {
+                                    from
+                                };
+                                ranges.push((from, to));
+                            }
+                        }
+                        first = false;
+                    }
+
+                    GlobToken::Class { negated, ranges }
+                }
+                | c => GlobToken::Literal(c),
+            };
+            tokens.push(token);
+        }
+
+        Ok(Glob { tokens })
+    }
+
+    /// Return the match of the pattern if it matches the whole of s, and `None` otherwise.
+    ///
+    /// The positions are the characters matched by anything but a star.
+    pub fn find(&self, s: &str) -> Option<Match> {
+        let text: Vec<char> = s.chars().collect();
+        let mut positions = Vec::new();
+        let (mut t, mut p) = (0, 0);
+        // last star seen, (the token after it, the text it resumes from and the positions before it)
+        let mut star: Option<(usize, usize, usize)> = None;
+
+        while t < text.len() {
+            match self.tokens.get(p) {
+                | Some(GlobToken::Star) => {
+                    star = Some((p + 1, t, positions.len()));
+                    p += 1;
+                    continue;
+                }
+                | Some(token) if token.matches(text[t]) => {
+                    positions.push(t);
+                    t += 1;
+                    p += 1;
+                    continue;
+                }
+                | _ => {}
+            }
+
+            // mismatch, let the last star swallow one more character and retry
+            let (after, resume, len) = star?;
+            p = after;
+            t = resume + 1;
+            star = Some((after, t, len));
+            positions.truncate(len);
+        }
+
+        // only stars may be left over once the text is used up
+        while self.tokens.get(p) == Some(&GlobToken::Star) {
+            p += 1;
+        }
+
+        (p == self.tokens.len()).then_some(Match {
+            score: 0,
+            positions,
+        })
+    }
 }
 
 /// Calculate the Levenshtein distance between strings s1 and s2.
@@ -9,40 +322,34 @@ pub fn exact_match(s1: &str, s2: &str) -> bool {
 ///   > Informally, the Levenshtein distance between two words is the minimum number of
 ///   > single-character edits (insertions, deletions or substitutions) required to change
 ///   > one word into the other.
+///
 /// https://en.wikipedia.org/wiki/Levenshtein_distance
 fn levenshtein_distance(s1: &str, s2: &str) -> usize {
-    let len1 = s1.chars().count();
-    let len2 = s2.chars().count();
+    let chars1: Vec<char> = s1.chars().collect();
+    let chars2: Vec<char> = s2.chars().collect();
 
-    // initialize n x m matrix for storing intermediate distances
+    // only the previous row of the n x m matrix of intermediate distances is needed,
     // where n is length of s1 and m is length of s2
-    let mut matrix = vec![vec![0; len2 + 1]; len1 + 1];
+    let mut previous: Vec<usize> = (0..=chars2.len()).collect();
+    let mut current: Vec<usize> = vec![0; chars2.len() + 1];
 
     // populate the matrix according to the 2 strings
-    for i in 0..=len1 {
-        for j in 0..=len2 {
-            if i == 0 {
-                matrix[i][j] = j;
-            } else if j == 0 {
-                matrix[i][j] = i;
-            } else {
-                // cost of editing string at this position, (i, j)
-                let cost = if s1.chars().nth(i - 1) == s2.chars().nth(j - 1) {
-                    0
-                } else {
-                    1
-                };
+    for (i, c1) in chars1.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, c2) in chars2.iter().enumerate() {
+            // cost of editing string at this position, (i, j)
+            let cost = if c1 == c2 { 0 } else { 1 };
 
-                // update matrix with minimum cost
-                matrix[i][j] = (matrix[i - 1][j] + 1)
-                    .min(matrix[i][j - 1] + 1)
-                    .min(matrix[i - 1][j - 1] + cost);
-            }
+            // update matrix with minimum cost
+            current[j + 1] = (previous[j + 1] + 1)
+                .min(current[j] + 1)
+                .min(previous[j] + cost);
         }
+        std::mem::swap(&mut previous, &mut current);
     }
 
     // return bottom right cell, (final Levenshtein distance)
-    matrix[len1][len2]
+    previous[chars2.len()]
 }
 
 /// Return `true` if s1 fuzzy matches s2, and `false` otherwise.
@@ -61,3 +368,409 @@ pub fn fuzzy_match(s1: &str, s2: &str) -> bool {
 
     ratio >= threshold
 }
+
+// scores of the fuzzy subsequence matcher, (after fzf)
+const SCORE_MATCH: i64 = 16;
+const SCORE_GAP_START: i64 = -3;
+const SCORE_GAP_EXTENSION: i64 = -1;
+const BONUS_BOUNDARY: i64 = SCORE_MATCH / 2;
+const BONUS_CAMEL: i64 = BONUS_BOUNDARY + SCORE_GAP_EXTENSION;
+const BONUS_CONSECUTIVE: i64 = -(SCORE_GAP_START + SCORE_GAP_EXTENSION);
+const BONUS_FIRST_CHAR_MULTIPLIER: i64 = 2;
+
+/// Return the bonus for matching character c, given the character before it, (if any).
+///
+/// Characters starting a word, (the first of the string or after a non-alphanumeric), get the
+/// boundary bonus, an uppercase letter after a lowercase one or a digit after a letter gets the
+/// camelCase bonus.
+fn char_bonus(prev: Option<char>, c: char) -> i64 {
+    match prev {
+        | None => BONUS_BOUNDARY,
+        | Some(prev) if !prev.is_alphanumeric() && c.is_alphanumeric() => {
+            BONUS_BOUNDARY
+        }
+        | Some(prev)
+            if (prev.is_lowercase() && c.is_uppercase())
+                || (prev.is_alphabetic() && c.is_numeric()) =>
+        {
+            BONUS_CAMEL
+        }
+        | _ => 0,
+    }
+}
+
+/// Return the match of the query s2 as a subsequence of s1, or `None` if the characters of s2 do
+/// not all appear in s1 in order.
+///
+/// Matching ignores case, the higher the score the better the match. Every matched character scores
+/// the same, plus a bonus if it starts a word, a camelCase hump or continues a run of consecutive
+/// matches, (the bonus of the query's first character counts double). Gaps between matched characters
+/// are penalized by their length. The best scoring alignment is found with dynamic programming.
+pub fn fuzzy_score(s1: &str, s2: &str) -> Option<Match> {
+    fuzzy_score_candidate(&Candidate::new(s1), s2, false)
+}
+
+/// Return the match of the query s2 as a subsequence of a prepared candidate, see `fuzzy_score`.
+///
+/// Case is only ignored unless `case_sensitive`.
+pub fn fuzzy_score_candidate(
+    candidate: &Candidate,
+    s2: &str,
+    case_sensitive: bool,
+) -> Option<Match> {
+    let text = &candidate.chars;
+    let (haystack, query): (&Vec<char>, Vec<char>) = if case_sensitive {
+        (text, s2.chars().collect())
+    } else {
+        (&candidate.lower_chars, s2.chars().map(lowercase).collect())
+    };
+
+    if query.is_empty() {
+        return Some(Match::default());
+    }
+
+    // most candidates do not match at all, rule them out before scoring, the first query character
+    // can match no earlier than the greedy match from the front and the last no later than the
+    // greedy match from the back
+    let mut forward = haystack.iter().enumerate();
+    let mut first = None;
+    for q in &query {
+        let (j, _) = forward.find(|(_, c)| *c == q)?;
+        first.get_or_insert(j);
+    }
+    let mut backward = haystack.iter().enumerate().rev();
+    let mut last = None;
+    for q in query.iter().rev() {
+        let (j, _) = backward.find(|(_, c)| *c == q)?;
+        last.get_or_insert(j);
+    }
+    let (first, last) = (first?, last?);
+
+    // only columns first..=last can take part in the match
+    let width = last - first + 1;
+    let bonuses: Vec<i64> = (first..=last)
+        .map(|j| char_bonus(j.checked_sub(1).map(|k| text[k]), text[j]))
+        .collect();
+
+    // best score of the query up to row i with its last character matched at each column,
+    // and the column the previous query character was matched at to get it, (row after row)
+    let mut scores: Vec<Option<i64>> = vec![None; query.len() * width];
+    let mut origins: Vec<usize> = vec![0; query.len() * width];
+
+    for (i, &q) in query.iter().enumerate() {
+        let row = i * width;
+        // best score of the previous row followed by a gap ending just before j, and where it started
+        let mut gapped: Option<(i64, usize)> = None;
+
+        for j in 0..width {
+            if haystack[first + j] == q {
+                if i == 0 {
+                    scores[j] = Some(
+                        SCORE_MATCH + bonuses[j] * BONUS_FIRST_CHAR_MULTIPLIER,
+                    );
+                } else {
+                    let consecutive = j.checked_sub(1).and_then(|k| {
+                        scores[row - width + k]
+                            .map(|score| (score + BONUS_CONSECUTIVE, k))
+                    });
+                    if let Some((score, k)) = best(consecutive, gapped) {
+                        scores[row + j] =
+                            Some(score + SCORE_MATCH + bonuses[j]);
+                        origins[row + j] = k;
+                    }
+                }
+            }
+
+            // extend the gap by column j, or start a new one after a match at j - 1
+            if i > 0 {
+                let extended =
+                    gapped.map(|(score, k)| (score + SCORE_GAP_EXTENSION, k));
+                let started = j.checked_sub(1).and_then(|k| {
+                    scores[row - width + k]
+                        .map(|score| (score + SCORE_GAP_START, k))
+                });
+                gapped = best(extended, started);
+            }
+        }
+    }
+
+    // walk back from the best match of the last query character
+    let row = (query.len() - 1) * width;
+    let (score, mut j) = scores[row..]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, score)| score.map(|score| (score, j)))
+        .max_by_key(|&(score, j)| (score, std::cmp::Reverse(j)))?;
+
+    let mut positions = vec![first + j; query.len()];
+    for i in (1..query.len()).rev() {
+        j = origins[i * width + j];
+        positions[i - 1] = first + j;
+    }
+
+    Some(Match { score, positions })
+}
+
+/// Return the higher scoring of two candidate `(score, origin)` pairs, (preferring a over ties).
+fn best(
+    a: Option<(i64, usize)>,
+    b: Option<(i64, usize)>,
+) -> Option<(i64, usize)> {
+    match (a, b) {
+        | (Some(a), Some(b)) => Some(if b.0 > a.0 { b } else { a }),
+        | (a, None) => a,
+        | (None, b) => b,
+    }
+}
+
+/// How a term of a query is matched against a string.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TermKind {
+    Fuzzy,
+    Exact,
+    Prefix,
+    Suffix,
+    Equal,
+}
+
+/// Single search term of a query, e.g. `!^foo`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Term {
+    pub kind: TermKind,
+    pub text: String,
+    pub negated: bool,
+}
+
+impl Term {
+    /// Parse a term, (without surrounding whitespace), written in the extended search syntax.
+    ///
+    /// Returns `None` if nothing is left to search for once the operators are removed.
+    fn parse(word: &str, default: TermKind) -> Option<Term> {
+        let mut text = word;
+        let mut kind = default;
+
+        let negated = text.starts_with('!');
+        if negated {
+            text = &text[1..];
+            // negated terms are exact unless asked otherwise
+            kind = TermKind::Exact;
+        }
+
+        if let Some(rest) = text.strip_prefix('\'') {
+            // quoted terms are exact, (or fuzzy where exact is the default)
+            text = rest;
+            kind = if default == TermKind::Exact {
+                TermKind::Fuzzy
+            } else {
+                TermKind::Exact
+            };
+        } else {
+            let prefix = text.starts_with('^');
+            let suffix =
+                text.len() > usize::from(prefix) && text.ends_with('$');
+            if prefix {
+                text = &text[1..];
+            }
+            if suffix {
+                text = &text[..text.len() - 1];
+            }
+            kind = match (prefix, suffix) {
+                | (true, true) => TermKind::Equal,
+                | (true, false) => TermKind::Prefix,
+                | (false, true) => TermKind::Suffix,
+                | (false, false) => kind,
+            };
+        }
+
+        (!text.is_empty()).then(|| Term {
+            kind,
+            text: text.to_string(),
+            negated,
+        })
+    }
+
+    /// Return the match of the term in a prepared candidate, and `None` if there is none.
+    ///
+    /// A negated term matches, highlighting nothing, wherever the plain term would not.
+    fn find(
+        &self,
+        candidate: &Candidate,
+        case_sensitive: bool,
+    ) -> Option<Match> {
+        let key = candidate.key(case_sensitive);
+        let found = match self.kind {
+            | TermKind::Fuzzy => {
+                fuzzy_score_candidate(candidate, &self.text, case_sensitive)
+                    .or_else(|| {
+                        // typos rank below every subsequence match and highlight nothing
+                        fuzzy_match(&self.text, key).then_some(Match {
+                            score: i64::MIN,
+                            positions: Vec::new(),
+                        })
+                    })
+            }
+            | TermKind::Exact => exact_match(key, &self.text),
+            | TermKind::Prefix => prefix_match(key, &self.text),
+            | TermKind::Suffix => key.ends_with(&self.text).then(|| {
+                let len = candidate.lower_chars.len();
+                Match {
+                    score: 0,
+                    positions: (len - self.text.chars().count()..len).collect(),
+                }
+            }),
+            | TermKind::Equal => (key == self.text).then(|| Match {
+                score: 0,
+                positions: (0..candidate.lower_chars.len()).collect(),
+            }),
+        };
+
+        if self.negated {
+            found.xor(Some(Match::default()))
+        } else {
+            found
+        }
+    }
+
+    /// Return `true` if every string the term matches is matched by `other` too.
+    ///
+    /// Only holds between plain, (not negated), terms whose text contains the other's where it
+    /// is anchored alike. Never holds for fuzzy terms, (a longer fuzzy term can be within the typo
+    /// threshold of strings a shorter one is not).
+    fn implies(&self, other: &Term) -> bool {
+        if self.negated || other.negated {
+            return false;
+        }
+        let (text, other_text) = (self.text.as_str(), other.text.as_str());
+        match (self.kind, other.kind) {
+            | (TermKind::Fuzzy, _) | (_, TermKind::Fuzzy) => false,
+            | (_, TermKind::Exact) => text.contains(other_text),
+            | (TermKind::Prefix | TermKind::Equal, TermKind::Prefix) => {
+                text.starts_with(other_text)
+            }
+            | (TermKind::Suffix | TermKind::Equal, TermKind::Suffix) => {
+                text.ends_with(other_text)
+            }
+            | (TermKind::Equal, TermKind::Equal) => text == other_text,
+            | _ => false,
+        }
+    }
+}
+
+/// Search query written in the extended search syntax, (after fzf).
+///
+/// Terms are separated by whitespace and must all match, terms joined by ` | ` match if any of
+/// them does. Each term is matched as the default kind unless written as
+/// - `'term`: exact match, (fuzzy where exact is the default)
+/// - `^term`: prefix match
+/// - `term$`: suffix match, (`^term$` matches the whole string)
+/// - `!term`: matches strings the term does not, (exact unless combined with the above)
+///
+/// A backslash keeps the following space part of a term.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Query {
+    pub groups: Vec<Vec<Term>>,
+}
+
+impl Query {
+    /// Parse a query, matching its plain terms as `default`.
+    pub fn parse(query: &str, default: TermKind) -> Query {
+        // split on unescaped whitespace
+        let mut words = Vec::new();
+        let mut word = String::new();
+        let mut chars = query.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                | '\\' if chars.peek() == Some(&' ') => {
+                    word.push(' ');
+                    chars.next();
+                }
+                | c if c.is_whitespace() => {
+                    if !word.is_empty() {
+                        words.push(std::mem::take(&mut word));
+                    }
+                }
+                | c => word.push(c),
+            }
+        }
+        if !word.is_empty() {
+            words.push(word);
+        }
+
+        let mut groups: Vec<Vec<Term>> = Vec::new();
+        let mut joined = false;
+        for word in words {
+            if word == "|" {
+                // the next term joins the group of the previous one
+                joined = !groups.is_empty();
+                continue;
+            }
+            if let Some(term) = Term::parse(&word, default) {
+                match groups.last_mut() {
+                    | Some(group) if joined => group.push(term),
+                    | _ => groups.push(vec![term]),
+                }
+            }
+            joined = false;
+        }
+
+        Query { groups }
+    }
+
+    /// Return `true` if every string the query matches is matched by `last` too.
+    ///
+    /// Holds when each term of `last` stands alone in its group and is implied by a term standing
+    /// alone in a group of this query, (so typing onto a plain term only hides more). Never holds
+    /// when `last` has alternatives, negated or fuzzy terms.
+    pub fn narrows(&self, last: &Query) -> bool {
+        let terms: Vec<&Term> = self
+            .groups
+            .iter()
+            .filter_map(|group| match group.as_slice() {
+                | [term] => Some(term),
+                | _ => None,
+            })
+            .collect();
+
+        last.groups.iter().all(|group| match group.as_slice() {
+            | [last] => terms.iter().any(|term| term.implies(last)),
+            | _ => false,
+        })
+    }
+
+    /// Return the match of the query in s, and `None` if any of its groups does not match.
+    ///
+    /// The score is the sum of the best score of each group, the positions those of every term
+    /// that matched. A query without terms matches everything. s is lowercased before matching,
+    /// (so terms are expected in lowercase).
+    pub fn find(&self, s: &str) -> Option<Match> {
+        self.find_candidate(&Candidate::new(s), false)
+    }
+
+    /// Return the match of the query in a prepared candidate, see `find`.
+    ///
+    /// The candidate is only lowercased unless `case_sensitive`, (terms are then matched as written).
+    pub fn find_candidate(
+        &self,
+        candidate: &Candidate,
+        case_sensitive: bool,
+    ) -> Option<Match> {
+        let mut found = Match::default();
+
+        for group in &self.groups {
+            let mut matches: Vec<Match> = group
+                .iter()
+                .filter_map(|term| term.find(candidate, case_sensitive))
+                .collect();
+            let best = matches.iter().map(|found| found.score).max()?;
+
+            found.score = found.score.saturating_add(best);
+            for matched in matches.iter_mut() {
+                found.positions.append(&mut matched.positions);
+            }
+        }
+
+        found.positions.sort_unstable();
+        found.positions.dedup();
+        Some(found)
+    }
+}
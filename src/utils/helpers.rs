@@ -1,20 +1,34 @@
 use crate::external::widget::Widget;
 
 /// Return the widget from model that has the same y value.
-pub fn find_widget_by_y(model: &Vec<Widget>, y: i32) -> Option<&Widget> {
+pub fn find_widget_by_y(model: &[Widget], y: i32) -> Option<&Widget> {
     model.iter().find(|&widget| match widget {
         | Widget::Input { y: widget_y, .. }
-        | Widget::Text { y: widget_y, .. } => *widget_y == y,
+        | Widget::Text { y: widget_y, .. }
+        | Widget::Checkbox { y: widget_y, .. }
+        | Widget::Select { y: widget_y, .. } => *widget_y == y,
     })
 }
 
 /// Return the mutable widget from model that has the same y value.
 pub fn find_widget_by_y_mut(
-    model: &mut Vec<Widget>,
+    model: &mut [Widget],
     y: i32,
 ) -> Option<&mut Widget> {
     model.iter_mut().find(|widget| match widget {
         | Widget::Input { y: widget_y, .. }
-        | Widget::Text { y: widget_y, .. } => *widget_y == y,
+        | Widget::Text { y: widget_y, .. }
+        | Widget::Checkbox { y: widget_y, .. }
+        | Widget::Select { y: widget_y, .. } => *widget_y == y,
+    })
+}
+
+/// Return the widget from model that has the given id.
+pub fn find_widget_by_id(model: &[Widget], id: usize) -> Option<&Widget> {
+    model.iter().find(|&widget| match widget {
+        | Widget::Input { id: widget_id, .. }
+        | Widget::Text { id: widget_id, .. }
+        | Widget::Checkbox { id: widget_id, .. }
+        | Widget::Select { id: widget_id, .. } => *widget_id == id,
     })
 }
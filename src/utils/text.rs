@@ -0,0 +1,55 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Return the number of terminal columns s takes up, (wide characters like CJK and emoji take 2).
+pub fn display_width(s: &str) -> usize {
+    UnicodeWidthStr::width(s)
+}
+
+/// Return the number of terminal columns c takes up, (0 for combining marks and control characters).
+pub fn char_width(c: char) -> usize {
+    UnicodeWidthChar::width(c).unwrap_or(0)
+}
+
+/// Return the number of graphemes in s, (user-perceived characters, e.g. "é" written as "e" and a
+/// combining accent is a single grapheme).
+pub fn grapheme_count(s: &str) -> usize {
+    s.graphemes(true).count()
+}
+
+/// Return the byte offset of the grapheme at the given index of s, (`s.len()` past its last one).
+pub fn grapheme_offset(s: &str, index: usize) -> usize {
+    s.grapheme_indices(true)
+        .nth(index)
+        .map_or(s.len(), |(offset, _)| offset)
+}
+
+/// Return the number of graphemes of s before the given byte offset.
+pub fn grapheme_index(s: &str, offset: usize) -> usize {
+    grapheme_count(&s[..offset])
+}
+
+/// Return the index of the grapheme of s drawn at the given column, (the number of graphemes in s if
+/// the column is past its end).
+pub fn grapheme_at_column(s: &str, column: usize) -> usize {
+    let mut width = 0;
+    for (index, grapheme) in s.graphemes(true).enumerate() {
+        width += display_width(grapheme);
+        if width > column {
+            return index;
+        }
+    }
+    grapheme_count(s)
+}
+
+/// Return the longest start of s that fits in the given number of columns, (whole graphemes only).
+pub fn fit_width(s: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (offset, grapheme) in s.grapheme_indices(true) {
+        used += display_width(grapheme);
+        if used > width {
+            return &s[..offset];
+        }
+    }
+    s
+}
@@ -1,56 +1,702 @@
 #[cfg(test)]
 mod model_test {
-    use stacklet::external::model::parse_stdout;
+    use stacklet::external::model::{parse_stdout, parse_stdout_as, Protocol};
+    use stacklet::external::widget::Widget;
+    use stacklet::interface::theme::{Color, Style};
+    use stacklet::utils::error::{ParseError, ParseErrorKind};
 
     #[test]
     fn parse_stdout_empty() {
-        assert!(parse_stdout("").0.is_empty());
+        assert!(parse_stdout("").unwrap().widgets.is_empty());
+    }
+
+    #[test]
+    fn parse_stdout_quit() {
+        // the command is returned, not run
+        let screen =
+            parse_stdout("TEXT(\"foo\")\nQUIT(\"echo bye\")\nTEXT(\"bar\")")
+                .unwrap();
+        assert!(screen.widgets.is_empty());
+        assert_eq!(screen.quit_command.as_deref(), Some("echo bye"));
+
+        assert!(parse_stdout("QUIT()").unwrap().quit_command.is_none());
     }
 
     #[test]
     fn parse_stdout_input_valid() {
-        assert!(!parse_stdout("INPUT()").0.is_empty());
-        assert!(!parse_stdout("INPUT(filter=\"exact\")").0.is_empty());
+        assert!(!parse_stdout("INPUT()").unwrap().widgets.is_empty());
+        assert!(!parse_stdout("INPUT(filter=\"exact\")")
+            .unwrap()
+            .widgets
+            .is_empty());
         assert!(!parse_stdout(
             "INPUT(filter=\"exact\" label=\"foo\" content=\"bar\")"
         )
-        .0
+        .unwrap()
+        .widgets
+        .is_empty());
+        assert!(!parse_stdout(
+            "INPUT(filter=\"exact\",label=\"foo\" content=\"bar\")"
+        )
+        .unwrap()
+        .widgets
         .is_empty());
-        assert!(!parse_stdout("INPUT(content=\"\")").0.is_empty());
+        assert!(!parse_stdout("INPUT(content=\"\")")
+            .unwrap()
+            .widgets
+            .is_empty());
     }
 
     #[test]
     fn parse_stdout_input_invalid() {
-        assert!(true);
-        // TODO: handle invalid input
-        // assert!(!parse_stdout("INPUT(filter=\"buzz\")").0.is_empty());
-        // assert!(!parse_stdout("INPUT(filter=\"exact\",label=\"foo\" content=\"bar\")").0.is_empty());
-        // assert!(!parse_stdout("INPUT(content=\")").0.is_empty());
+        assert_eq!(
+            parse_stdout("INPUT(filter=\"buzz\")").unwrap_err(),
+            vec![ParseError {
+                line: 1,
+                column: 7,
+                kind: ParseErrorKind::InvalidValue {
+                    param: "filter".to_string(),
+                    value: "buzz".to_string(),
+                },
+                snippet: "INPUT(filter=\"buzz\")".to_string(),
+            }]
+        );
+        assert!(parse_stdout("INPUT(max_width=\"wide\")").is_err());
+        assert!(parse_stdout("INPUT(case=\"upper\")").is_err());
+        assert!(parse_stdout("INPUT(normalize=\"yes\")").is_err());
+        assert!(parse_stdout("INPUT(content=\")").is_err());
+        assert!(parse_stdout("INPUT(label)").is_err());
+    }
+
+    #[test]
+    fn parse_stdout_input_name() {
+        let widgets = parse_stdout("INPUT(name=\"query\" content=\"foo\")")
+            .unwrap()
+            .widgets;
+        assert!(
+            matches!(&widgets[0], Widget::Input { name, content, .. } if name == "query" && content == "foo")
+        );
     }
 
     #[test]
     fn parse_stdout_text_valid() {
-        assert!(!parse_stdout("TEXT(\"exact\")").0.is_empty());
-        assert!(!parse_stdout("TEXT(\"\")").0.is_empty());
+        assert!(!parse_stdout("TEXT(\"exact\")").unwrap().widgets.is_empty());
+        assert!(!parse_stdout("TEXT(\"\")").unwrap().widgets.is_empty());
+    }
+
+    #[test]
+    fn parse_stdout_text_value() {
+        let stdout = "TEXT(\"Firefox\", value=\"firefox --new-window\", key=\"ff\")\nTEXT(\"Chromium\")";
+        let widgets = parse_stdout(stdout).unwrap().widgets;
+
+        match &widgets[0] {
+            | Widget::Text {
+                content,
+                value,
+                key,
+                ..
+            } => {
+                assert_eq!(content, "Firefox");
+                assert_eq!(value, "firefox --new-window");
+                assert_eq!(key, "ff");
+            }
+            | _ => panic!("expected text widget"),
+        }
+        match &widgets[1] {
+            | Widget::Text {
+                content,
+                value,
+                key,
+                ..
+            } => {
+                assert_eq!(content, "Chromium");
+                assert_eq!(value, "Chromium");
+                assert!(key.is_empty());
+            }
+            | _ => panic!("expected text widget"),
+        }
+
+        let json =
+            "{\"type\":\"text\",\"content\":\"Firefox\",\"value\":\"firefox\"}";
+        assert!(matches!(
+            &parse_stdout_as(json, Protocol::Json).unwrap().widgets[0],
+            Widget::Text { value, .. } if value == "firefox"
+        ));
     }
 
     #[test]
     fn parse_stdout_text_invalid() {
-        // TODO: handle more invalid input
-        assert!(parse_stdout("TEXT()").0.is_empty());
+        let errors = parse_stdout("TEXT()").unwrap_err();
+        assert_eq!(
+            errors[0].kind,
+            ParseErrorKind::MissingArgument("content".to_string())
+        );
+
+        let errors = parse_stdout("TEXT(\"foo\"").unwrap_err();
+        assert_eq!(errors[0].kind, ParseErrorKind::UnexpectedEnd);
+        assert_eq!(errors[0].column, 11);
+
+        let errors = parse_stdout("TXT(\"foo\")").unwrap_err();
+        assert_eq!(
+            errors[0].kind,
+            ParseErrorKind::UnknownWidget("txt".to_string())
+        );
+
+        let errors = parse_stdout("plain words").unwrap_err();
+        assert_eq!(errors[0].kind, ParseErrorKind::Expected('('));
+        assert_eq!(errors[0].column, 6);
+    }
+
+    #[test]
+    fn parse_stdout_style() {
+        let widgets = parse_stdout(
+            "INPUT(fg=\"red\" bold=true)\nTEXT(\"foo\", bg=\"208\" underline=true)",
+        )
+        .unwrap()
+        .widgets;
+        let red_bold = Style {
+            fg: Some(Color::Red),
+            bold: Some(true),
+            ..Style::default()
+        };
+        assert!(
+            matches!(&widgets[0], Widget::Input { style, .. } if *style == red_bold)
+        );
+        let underlined = Style {
+            bg: Some(Color::Indexed(208)),
+            underline: Some(true),
+            ..Style::default()
+        };
+        assert!(
+            matches!(&widgets[1], Widget::Text { style, .. } if *style == underlined)
+        );
+
+        let json = "{\"type\":\"text\",\"content\":\"foo\",\"dim\":true}";
+        let widgets = parse_stdout_as(json, Protocol::Json).unwrap().widgets;
+        assert!(
+            matches!(&widgets[0], Widget::Text { style, .. } if style.dim == Some(true))
+        );
+
+        assert!(parse_stdout("TEXT(\"foo\" fg=\"purple\")").is_err());
+        assert!(parse_stdout("INPUT(bg=\"256\")").is_err());
+        assert!(parse_stdout("INPUT(dim=\"yes\")").is_err());
+    }
+
+    #[test]
+    fn parse_stdout_checkbox() {
+        let stdout = "CHECKBOX(label=\"Wi-Fi\", checked=\"true\", name=\"wifi\")\nCHECKBOX(label=\"Bluetooth\")";
+        let widgets = parse_stdout(stdout).unwrap().widgets;
+
+        match &widgets[0] {
+            | Widget::Checkbox {
+                label,
+                name,
+                checked,
+                ..
+            } => {
+                assert_eq!(label, "Wi-Fi");
+                assert_eq!(name, "wifi");
+                assert!(*checked);
+            }
+            | _ => panic!("expected checkbox widget"),
+        }
+        match &widgets[1] {
+            | Widget::Checkbox { name, checked, .. } => {
+                assert_eq!(name, "Bluetooth");
+                assert!(!*checked);
+            }
+            | _ => panic!("expected checkbox widget"),
+        }
+
+        let json = "{\"type\":\"checkbox\",\"label\":\"x\",\"checked\":true}";
+        assert!(matches!(
+            &parse_stdout_as(json, Protocol::Json).unwrap().widgets[0],
+            Widget::Checkbox { checked: true, .. }
+        ));
+
+        let errors = parse_stdout("CHECKBOX(checked=\"yes\")").unwrap_err();
+        assert_eq!(errors[0].column, 10);
+    }
+
+    #[test]
+    fn parse_stdout_select() {
+        let stdout = "SELECT(label=\"Theme: \", name=\"theme\", options=\"light|dark|auto\", selected=\"dark\")";
+        match &parse_stdout(stdout).unwrap().widgets[0] {
+            | Widget::Select {
+                label,
+                name,
+                options,
+                selected,
+                ..
+            } => {
+                assert_eq!(label, "Theme: ");
+                assert_eq!(name, "theme");
+                assert_eq!(options, &["light", "dark", "auto"]);
+                assert_eq!(*selected, 1);
+            }
+            | _ => panic!("expected select widget"),
+        }
+
+        // json options are taken as written, (a `|` does not split them)
+        let json = "{\"type\":\"select\",\"name\":\"n\",\"options\":[\"a|b\",\"c\"],\"selected\":\"c\"}";
+        assert!(matches!(
+            &parse_stdout_as(json, Protocol::Json).unwrap().widgets[0],
+            Widget::Select { selected: 1, options, .. } if options == &["a|b", "c"]
+        ));
+
+        let errors = parse_stdout("SELECT(name=\"n\")").unwrap_err();
+        assert_eq!(
+            errors[0].kind,
+            ParseErrorKind::MissingArgument("options".to_string())
+        );
+        let errors = parse_stdout("SELECT(options=\"a|b\", selected=\"c\")")
+            .unwrap_err();
+        assert_eq!(errors[0].column, 23);
+    }
+
+    #[test]
+    fn parse_stdout_rows() {
+        let screen =
+            parse_stdout("DATA(\"x\")\nTEXT(\"a\")\n\nTEXT(\"b\")").unwrap();
+
+        assert_eq!(screen.data, "x");
+        assert!(matches!(
+            screen.widgets[0],
+            Widget::Text { y: 0, id: 0, .. }
+        ));
+        assert!(matches!(
+            screen.widgets[1],
+            Widget::Text { y: 2, id: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn parse_stdout_multi_select() {
+        assert!(!parse_stdout("TEXT(\"foo\")").unwrap().multi_select);
+
+        let screen =
+            parse_stdout("MULTISELECT()\nTEXT(\"foo\")\nTEXT(\"bar\")")
+                .unwrap();
+        assert!(screen.multi_select);
+        assert_eq!(screen.widgets.len(), 2);
+        assert!(matches!(
+            &screen.widgets[0],
+            Widget::Text {
+                y: 0,
+                marked: false,
+                ..
+            }
+        ));
+
+        let json = "{\"type\":\"multiselect\"}\n{\"type\":\"text\",\"content\":\"foo\"}";
+        assert!(parse_stdout_as(json, Protocol::Json).unwrap().multi_select);
     }
 
     #[test]
     fn parse_stdout_full() {
-        assert!(!parse_stdout("INPUT()\nTEXT()").0.is_empty());
-        assert!(!parse_stdout("INPUT(filter=\"exact\")\nTEXT()\nTEXT()")
-            .0
+        assert!(!parse_stdout("INPUT()\nTEXT(\"foo\")")
+            .unwrap()
+            .widgets
             .is_empty());
-        assert!(!parse_stdout("INPUT(filter=\"exact\" label=\"foo\" content=\"bar\")\nTEXT(\"foobar\")\nTEXT()").0.is_empty());
+        assert!(!parse_stdout("INPUT(filter=\"exact\")\n\nTEXT(\"foo\")")
+            .unwrap()
+            .widgets
+            .is_empty());
+        assert!(!parse_stdout("INPUT(filter=\"exact\" label=\"foo\" content=\"bar\")\nTEXT(\"foobar\")\nTEXT(\"\")").unwrap().widgets.is_empty());
         assert!(!parse_stdout(
             "INPUT(content=\"\")\nINPUT(label=\"foo\")\nINPUT()"
         )
-        .0
+        .unwrap()
+        .widgets
         .is_empty());
     }
+
+    #[test]
+    fn parse_stdout_full_invalid() {
+        let errors =
+            parse_stdout("INPUT()\nTEXT()\nTEXT(\"ok\")\nTEXT(\"\\q\")")
+                .unwrap_err();
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].line, 2);
+        assert_eq!(errors[1].line, 4);
+        assert_eq!(errors[1].column, 7);
+        assert_eq!(
+            errors[1].kind,
+            ParseErrorKind::InvalidEscape("\\q".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_stdout_json_valid() {
+        let stdout = "{\"type\":\"input\",\"label\":\"a, b\",\"max_width\":8}\n{\"type\":\"text\",\"content\":\"say \\\"hi\\\"), ok\"}\n{\"type\":\"data\",\"content\":\"foo\"}";
+        let screen = parse_stdout_as(stdout, Protocol::Json).unwrap();
+        let (widgets, data) = (screen.widgets, screen.data);
+
+        assert_eq!(widgets.len(), 2);
+        assert_eq!(data, "foo");
+        match &widgets[0] {
+            | Widget::Input {
+                label, max_width, ..
+            } => {
+                assert_eq!(label, "a, b");
+                assert_eq!(*max_width, 8);
+            }
+            | _ => panic!("expected input widget"),
+        }
+        match &widgets[1] {
+            | Widget::Text { content, .. } => {
+                assert_eq!(content, "say \"hi\"), ok")
+            }
+            | _ => panic!("expected text widget"),
+        }
+    }
+
+    #[test]
+    fn parse_stdout_json_invalid() {
+        assert!(parse_stdout_as("TEXT(\"foo\")", Protocol::Json).is_err());
+        assert!(parse_stdout_as("{\"type\":\"text\"}", Protocol::Json).is_err());
+        assert!(parse_stdout_as("{\"type\":\"buzz\"}", Protocol::Json).is_err());
+        assert!(parse_stdout_as("[1, 2]", Protocol::Json).is_err());
+
+        let errors = parse_stdout_as("{\"type\":\"text\",}", Protocol::Json)
+            .unwrap_err();
+        assert!(matches!(errors[0].kind, ParseErrorKind::InvalidJson(_)));
+        assert_eq!(errors[0].column, 16);
+
+        // invalid parameters point at their key, (not a value that looks like it)
+        let json =
+            "{\"type\":\"checkbox\",\"label\":\"checked\",\"checked\":\"yes\"}";
+        let errors = parse_stdout_as(json, Protocol::Json).unwrap_err();
+        assert_eq!(errors[0].column, 38);
+        let errors = parse_stdout_as("  {\"type\":\"buzz\"}", Protocol::Json)
+            .unwrap_err();
+        assert_eq!(errors[0].column, 4);
+        let errors = parse_stdout_as("  {\"content\":\"a\"}", Protocol::Json)
+            .unwrap_err();
+        assert_eq!(errors[0].column, 3);
+    }
+
+    #[test]
+    fn parse_stdout_protocol_header() {
+        let json =
+            "PROTOCOL(\"json\")\n{\"type\":\"text\",\"content\":\"foo\"}";
+        assert_eq!(parse_stdout(json).unwrap().widgets.len(), 1);
+
+        let errors = parse_stdout("PROTOCOL(\"yaml\")").unwrap_err();
+        assert_eq!(
+            errors[0].kind,
+            ParseErrorKind::InvalidValue {
+                param: "protocol".to_string(),
+                value: "yaml".to_string(),
+            }
+        );
+        assert_eq!(errors[0].column, 10);
+        let errors = parse_stdout("PROTOCOL()").unwrap_err();
+        assert_eq!(
+            errors[0].kind,
+            ParseErrorKind::MissingArgument("protocol".to_string())
+        );
+
+        let macros = "PROTOCOL(\"macro\")\nTEXT(\"foo\")";
+        assert_eq!(
+            parse_stdout_as(macros, Protocol::Json)
+                .unwrap()
+                .widgets
+                .len(),
+            1
+        );
+    }
+}
+
+#[cfg(test)]
+mod syntax_test {
+    use stacklet::external::model::parse_stdout;
+    use stacklet::external::syntax::{escape, parse_macro, Arg};
+    use stacklet::external::widget::Widget;
+
+    fn text_content(line: &str) -> String {
+        match parse_stdout(line).unwrap().widgets.first() {
+            | Some(Widget::Text { content, .. }) => content.clone(),
+            | _ => panic!("expected text widget for {}", line),
+        }
+    }
+
+    #[test]
+    fn escape_round_trip() {
+        let samples = [
+            "plain",
+            "say \"hi\"",
+            "a, b",
+            "back\\slash",
+            "trailing \\",
+            "tab\tand\nnewline\r",
+            "close) paren",
+            "unicode: caf\u{e9} \u{65e5}\u{672c} \u{1f600}",
+            "bell \u{7}",
+            "",
+        ];
+
+        for sample in samples {
+            let line = format!("TEXT(\"{}\")", escape(sample));
+            assert_eq!(text_content(&line), sample);
+        }
+    }
+
+    #[test]
+    fn escape_sequences() {
+        assert_eq!(text_content(r#"TEXT("say \"hi\"")"#), "say \"hi\"");
+        assert_eq!(text_content(r#"TEXT("a\\b")"#), "a\\b");
+        assert_eq!(text_content(r#"TEXT("a\nb\tc")"#), "a\nb\tc");
+        assert_eq!(text_content(r#"TEXT("café")"#), "caf\u{e9}");
+        assert_eq!(text_content(r#"TEXT("\u{1F600}")"#), "\u{1f600}");
+    }
+
+    #[test]
+    fn escape_sequences_invalid() {
+        assert!(parse_stdout(r#"TEXT("\q")"#).is_err());
+        assert!(parse_stdout(r#"TEXT("\u00")"#).is_err());
+        assert!(parse_stdout(r#"TEXT("\u{}")"#).is_err());
+        assert!(parse_stdout(r#"TEXT("\u{d800}")"#).is_err());
+        assert!(parse_stdout(r#"TEXT("unterminated\")"#).is_err());
+    }
+
+    #[test]
+    fn parse_macro_params() {
+        let parsed =
+            parse_macro(r#"INPUT(label="a, b", max_width=20 content="x)")"#)
+                .unwrap();
+
+        assert_eq!(parsed.name, "INPUT");
+        assert_eq!(
+            parsed.args,
+            vec![
+                Arg {
+                    name: Some("label".to_string()),
+                    value: "a, b".to_string(),
+                    column: 7,
+                },
+                Arg {
+                    name: Some("max_width".to_string()),
+                    value: "20".to_string(),
+                    column: 21,
+                },
+                Arg {
+                    name: Some("content".to_string()),
+                    value: "x)".to_string(),
+                    column: 34,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_macro_invalid() {
+        assert!(parse_macro("").is_err());
+        assert!(parse_macro("TEXT").is_err());
+        assert!(parse_macro("TEXT(\"foo\"").is_err());
+        assert!(parse_macro("TEXT(\"foo\") trailing").is_err());
+        assert!(parse_macro("INPUT(label)").is_err());
+    }
+}
+
+#[cfg(test)]
+mod exec_test {
+    use stacklet::external::exec::{
+        encode_checked, encode_inputs, encode_selected, run_executable,
+        Coprocess, Encoding, Runtime,
+    };
+    use stacklet::external::model::Protocol;
+    use stacklet::external::widget::Widget;
+    use stacklet::utils::error::RunError;
+
+    fn runtime() -> Runtime {
+        Runtime {
+            input: "foo".to_string(),
+            input_content: "a:b".to_string(),
+            selection: "say \"hi\"".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn runtime_to_event() {
+        let event = runtime().to_event();
+        let parsed: serde_json::Value = serde_json::from_str(&event).unwrap();
+
+        assert!(!event.contains('\n'));
+        assert_eq!(parsed["INPUT"], "foo");
+        assert_eq!(parsed["INPUT_CONTENT"], "a:b");
+        assert_eq!(parsed["SELECTION"], "say \"hi\"");
+        assert_eq!(parsed["SELECTION_KEY"], "");
+        assert_eq!(parsed["CHECKED"], "");
+        assert_eq!(parsed["SELECTED"], "");
+        assert_eq!(parsed["DATA"], "");
+    }
+
+    #[test]
+    fn run_executable_env() {
+        let widgets = run_executable(
+            "echo \"TEXT(\\\"$INPUT_CONTENT\\\")\"",
+            &Protocol::Macro,
+            &runtime(),
+        )
+        .unwrap()
+        .widgets;
+
+        assert!(
+            matches!(&widgets[0], Widget::Text { content, .. } if content == "a:b")
+        );
+    }
+
+    #[test]
+    fn run_executable_named_inputs() {
+        let runtime = Runtime {
+            inputs: vec![
+                ("query".to_string(), "foo".to_string()),
+                ("file-name".to_string(), "bar".to_string()),
+                (String::new(), "baz".to_string()),
+            ],
+            ..Default::default()
+        };
+        let widgets = run_executable(
+            "echo \"TEXT(\\\"$STACKLET_INPUT_query,$STACKLET_INPUT_file_name\\\")\"",
+            &Protocol::Macro,
+            &runtime,
+        )
+        .unwrap()
+        .widgets;
+
+        assert!(
+            matches!(&widgets[0], Widget::Text { content, .. } if content == "foo,bar")
+        );
+    }
+
+    #[test]
+    fn run_executable_failure() {
+        assert_eq!(
+            run_executable(
+                "echo oops >&2; exit 3",
+                &Protocol::Macro,
+                &runtime()
+            )
+            .unwrap_err(),
+            RunError::Failed("oops\n".to_string())
+        );
+        assert!(matches!(
+            run_executable("echo 'TEXT('", &Protocol::Macro, &runtime()),
+            Err(RunError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn encode_inputs_escaped() {
+        let inputs = vec![
+            ("query".to_string(), "a:b".to_string()),
+            (String::new(), "c&d=\"é\"".to_string()),
+        ];
+
+        assert_eq!(encode_inputs(&inputs, &Encoding::Plain), "a:b:c&d=\"é\"");
+        assert_eq!(
+            encode_inputs(&inputs, &Encoding::Url),
+            "query=a%3Ab&1=c%26d%3D%22%C3%A9%22"
+        );
+
+        let parsed: serde_json::Value =
+            serde_json::from_str(&encode_inputs(&inputs, &Encoding::Json))
+                .unwrap();
+        assert_eq!(parsed["query"], "a:b");
+        assert_eq!(parsed["1"], "c&d=\"é\"");
+    }
+
+    #[test]
+    fn encode_checked_escaped() {
+        let names = vec!["a,b".to_string(), "c".to_string()];
+
+        assert_eq!(encode_checked(&names, &Encoding::Plain), "a,b,c");
+        assert_eq!(encode_checked(&names, &Encoding::Url), "a%2Cb,c");
+        assert_eq!(encode_checked(&names, &Encoding::Json), "[\"a,b\",\"c\"]");
+        assert_eq!(encode_checked(&[], &Encoding::Json), "[]");
+    }
+
+    #[test]
+    fn encode_selected_escaped() {
+        let selected = vec![
+            ("a=b".to_string(), "c\nd".to_string()),
+            ("size".to_string(), "big".to_string()),
+        ];
+
+        assert_eq!(
+            encode_selected(&selected, &Encoding::Plain),
+            "a=b=c\nd\nsize=big"
+        );
+        assert_eq!(
+            encode_selected(&selected, &Encoding::Url),
+            "a%3Db=c%0Ad&size=big"
+        );
+
+        let parsed: serde_json::Value =
+            serde_json::from_str(&encode_selected(&selected, &Encoding::Json))
+                .unwrap();
+        assert_eq!(parsed["a=b"], "c\nd");
+        assert_eq!(parsed["size"], "big");
+    }
+
+    #[test]
+    fn coprocess_frames() {
+        // answers each event with a count of the events seen so far
+        let script = "n=0; while read -r event; do n=$((n+1)); \
+                      echo \"TEXT(\\\"$n\\\")\"; echo \"TEXT(\\\"x\\\")\"; echo ---; done";
+        let mut coprocess = Coprocess::spawn(script, "---").unwrap();
+
+        for expected in ["1", "2", "3"] {
+            let widgets = coprocess
+                .request(&Protocol::Macro, &runtime())
+                .unwrap()
+                .widgets;
+            assert_eq!(widgets.len(), 2);
+            assert!(
+                matches!(&widgets[0], Widget::Text { content, .. } if content == expected)
+            );
+        }
+    }
+
+    #[test]
+    fn coprocess_exit() {
+        // a frame cut short by the executable exiting ends the program
+        let mut coprocess = Coprocess::spawn("read -r event", "---").unwrap();
+        let widgets = coprocess
+            .request(&Protocol::Macro, &runtime())
+            .unwrap()
+            .widgets;
+        assert!(widgets.is_empty());
+    }
+
+    #[test]
+    fn coprocess_failure() {
+        let mut coprocess =
+            Coprocess::spawn("read -r event; echo oops >&2; exit 1", "---")
+                .unwrap();
+        assert_eq!(
+            coprocess.request(&Protocol::Macro, &runtime()).unwrap_err(),
+            RunError::Failed("oops\n".to_string())
+        );
+    }
+
+    #[test]
+    fn coprocess_large_stderr() {
+        // more stderr than a pipe holds before answering, then ignores the end of its stdin
+        let script = "read -r event; head -c 200000 /dev/zero >&2; \
+                      echo 'TEXT(\"ok\")'; echo ---; sleep 10";
+        let mut coprocess = Coprocess::spawn(script, "---").unwrap();
+        let widgets = coprocess
+            .request(&Protocol::Macro, &runtime())
+            .unwrap()
+            .widgets;
+        assert_eq!(widgets.len(), 1);
+
+        let started = std::time::Instant::now();
+        drop(coprocess);
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
 }
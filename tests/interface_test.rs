@@ -0,0 +1,1066 @@
+#[cfg(test)]
+mod window_test {
+    use pancurses::Input;
+    use stacklet::external::model::parse_stdout;
+    use stacklet::external::widget::Widget;
+    use stacklet::interface::backend::{Headless, Mouse};
+    use stacklet::interface::keymap::Keymap;
+    use stacklet::interface::theme::{Color, Style, Theme};
+    use stacklet::interface::window::{init, BreakCondition, Options};
+    use stacklet::utils::helpers::find_widget_by_id;
+
+    fn model(stdout: &str) -> Vec<Widget> {
+        parse_stdout(stdout).unwrap().widgets
+    }
+
+    fn keys(typed: &str) -> Vec<Input> {
+        typed.chars().map(Input::Character).collect()
+    }
+
+    #[test]
+    fn filter_and_select() {
+        let mut model = model(
+            "INPUT(filter=\"exact\")\nTEXT(\"foobar\")\nTEXT(\"baz\")\nTEXT(\"food\")",
+        );
+        let mut script = keys("fo");
+        script.extend([Input::KeyDown, Input::Character('\n')]);
+        let mut backend = Headless::new(10, 20, script);
+
+        let (break_condition, id) =
+            init(&mut backend, &mut model, &Options::default());
+
+        assert_eq!(break_condition, BreakCondition::SELECTION);
+        assert!(
+            matches!(find_widget_by_id(&model, id).unwrap(), Widget::Text { value, .. } if value == "foobar")
+        );
+        assert_eq!(backend.snapshot(), "  fo\n> foobar\n  food");
+        assert_eq!(backend.styled(1, Theme::default().highlight), "fo");
+        assert_eq!(backend.styled(2, Theme::default().highlight), "fo");
+    }
+
+    #[test]
+    fn fuzzy_ranking() {
+        let mut model = model(
+            "INPUT(filter=\"fuzzy\")\nTEXT(\"office\")\nTEXT(\"bar\")\nTEXT(\"firefox\")\nCHECKBOX(label=\"c\")",
+        );
+        let mut script = keys("ff");
+        script.extend([Input::KeyDown, Input::Character('\n')]);
+        let mut backend = Headless::new(10, 20, script);
+
+        let (break_condition, id) =
+            init(&mut backend, &mut model, &Options::default());
+
+        assert_eq!(break_condition, BreakCondition::SELECTION);
+        assert!(
+            matches!(find_widget_by_id(&model, id).unwrap(), Widget::Text { value, .. } if value == "firefox")
+        );
+        assert_eq!(backend.snapshot(), "  ff\n> firefox\n  office\n  [ ] c");
+        assert_eq!(backend.styled(1, Theme::default().highlight), "ff");
+        assert_eq!(backend.styled(2, Theme::default().highlight), "ff");
+        assert_eq!(backend.styled(3, Theme::default().highlight), "");
+    }
+
+    #[test]
+    fn extended_query() {
+        let mut model = model(
+            "INPUT(filter=\"fuzzy\")\nTEXT(\"src/main.rs\")\nTEXT(\"src/lib.rs\")\nTEXT(\"tests/main.rs\")\nTEXT(\"build.py\")",
+        );
+        let mut backend = Headless::new(10, 40, keys("rs$ !test | py$"));
+
+        init(&mut backend, &mut model, &Options::default());
+
+        assert_eq!(
+            backend.snapshot(),
+            "  rs$ !test | py$\n  src/main.rs\n  src/lib.rs"
+        );
+        assert_eq!(backend.styled(1, Theme::default().highlight), "rs");
+    }
+
+    #[test]
+    fn incremental_filter() {
+        let stdout = "INPUT(filter=\"fuzzy\")\nTEXT(\"foo\")\nTEXT(\"far\")\nTEXT(\"bar\")";
+
+        // shrinking the search string brings back what growing it hid
+        let mut script = keys("fo");
+        script.extend([Input::KeyBackspace, Input::Character('a')]);
+        let mut backend = Headless::new(10, 20, script);
+        init(&mut backend, &mut model(stdout), &Options::default());
+        assert_eq!(backend.snapshot(), "  fa\n  far");
+
+        // growing a negated term can show more
+        let mut backend = Headless::new(10, 20, keys("!fo"));
+        init(&mut backend, &mut model(stdout), &Options::default());
+        assert_eq!(backend.snapshot(), "  !fo\n  far\n  bar");
+
+        // as can growing past a suffix anchor
+        let stdout = "INPUT(filter=\"fuzzy\")\nTEXT(\"foo\")\nTEXT(\"co$t a\")";
+        let mut backend = Headless::new(10, 20, keys("o$a"));
+        init(&mut backend, &mut model(stdout), &Options::default());
+        assert_eq!(backend.snapshot(), "  o$a\n  co$t a");
+
+        // and growing a quoted term of an exact filter, (fuzzy, so within the typo threshold)
+        let stdout = "INPUT(filter=\"exact\")\nTEXT(\"xbc\")\nTEXT(\"foo\")";
+        let mut backend = Headless::new(10, 20, keys("'abc"));
+        init(&mut backend, &mut model(stdout), &Options::default());
+        assert_eq!(backend.snapshot(), "  'abc\n  xbc");
+    }
+
+    #[test]
+    fn smart_case() {
+        let stdout = "INPUT(filter=\"exact\" case=\"smart\")\nTEXT(\"README.md\")\nTEXT(\"readme.txt\")";
+
+        let mut backend = Headless::new(10, 20, keys("read"));
+        init(&mut backend, &mut model(stdout), &Options::default());
+        assert_eq!(backend.snapshot(), "  read\n  README.md\n  readme.txt");
+
+        let mut backend = Headless::new(10, 20, keys("READ"));
+        init(&mut backend, &mut model(stdout), &Options::default());
+        assert_eq!(backend.snapshot(), "  READ\n  README.md");
+
+        let stdout = stdout.replace("smart", "sensitive");
+        let mut backend = Headless::new(10, 20, keys("read"));
+        init(&mut backend, &mut model(&stdout), &Options::default());
+        assert_eq!(backend.snapshot(), "  read\n  readme.txt");
+    }
+
+    #[test]
+    fn normalize_diacritics() {
+        let mut model = model(
+            "INPUT(filter=\"fuzzy\" normalize=\"true\")\nTEXT(\"Ærø\")\nTEXT(\"Café Zoë\")\nTEXT(\"cafeteria\")",
+        );
+        let mut backend = Headless::new(10, 20, keys("cafe z"));
+
+        init(&mut backend, &mut model, &Options::default());
+
+        assert_eq!(backend.snapshot(), "  cafe z\n  Café Zoë");
+        assert_eq!(backend.styled(1, Theme::default().highlight), "CaféZ");
+    }
+
+    #[test]
+    fn control_characters() {
+        let mut model = model("TEXT(\"a\\tb\\u001b[31mc\\nd\")");
+        let mut backend = Headless::new(10, 20, Vec::new());
+
+        init(&mut backend, &mut model, &Options::default());
+
+        assert_eq!(backend.snapshot(), "> ab[31mcd");
+    }
+
+    #[test]
+    fn viewport_only() {
+        let stdout = (0..100)
+            .map(|i| format!("TEXT(\"row {}\")", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut model = model(&format!("INPUT(filter=\"exact\")\n{}", stdout));
+        let mut backend = Headless::new(3, 20, keys("9"));
+
+        init(&mut backend, &mut model, &Options::default());
+
+        assert_eq!(backend.snapshot(), "  9\n  row 9\n  row 19");
+    }
+
+    #[test]
+    fn regex_invalid() {
+        let mut model = model(
+            "INPUT(filter=\"regex\")\nTEXT(\"GET /a 200\")\nTEXT(\"GET /b 404\")",
+        );
+        let mut backend = Headless::new(10, 60, keys("4(0"));
+
+        init(&mut backend, &mut model, &Options::default());
+
+        // an unclosed group keeps the list as it was after "4"
+        assert_eq!(
+            backend.snapshot(),
+            "  4(0 (invalid regex: unclosed group)\n  GET /b 404"
+        );
+    }
+
+    #[test]
+    fn regex_and_glob() {
+        let stdout = "INPUT(filter=\"regex\")\nTEXT(\"GET /a 200\")\nTEXT(\"GET /b 404\")";
+        let mut backend = Headless::new(10, 60, keys(r"\d0{2}"));
+        init(&mut backend, &mut model(stdout), &Options::default());
+        assert_eq!(backend.snapshot(), "  \\d0{2}\n  GET /a 200");
+        assert_eq!(backend.styled(1, Theme::default().highlight), "200");
+
+        let stdout = stdout.replace("regex", "glob");
+        let mut backend = Headless::new(10, 60, keys("*/b*"));
+        init(&mut backend, &mut model(&stdout), &Options::default());
+        assert_eq!(backend.snapshot(), "  */b*\n  GET /b 404");
+    }
+
+    #[test]
+    fn input_enter() {
+        let mut model =
+            model("INPUT(label=\"> \" content=\"ab\")\nTEXT(\"x\")");
+        let mut script = vec![Input::KeyBackspace];
+        script.extend(keys("c\n"));
+        let mut backend = Headless::new(10, 20, script);
+
+        let (break_condition, id) =
+            init(&mut backend, &mut model, &Options::default());
+
+        assert_eq!(break_condition, BreakCondition::INPUT);
+        assert!(
+            matches!(find_widget_by_id(&model, id).unwrap(), Widget::Input { content, .. } if content == "ac")
+        );
+        assert_eq!(backend.row(0), "  > ac");
+        assert_eq!(backend.cursor(), (0, 6));
+        assert!(backend.cursor_visible());
+    }
+
+    #[test]
+    fn input_multi_byte() {
+        let mut model = model("INPUT(label=\"> \")\nTEXT(\"x\")");
+        let mut script = keys("日本");
+        script.push(Input::KeyLeft);
+        script.extend(keys("é"));
+        let mut backend = Headless::new(10, 20, script);
+
+        init(&mut backend, &mut model, &Options::default());
+
+        assert!(matches!(
+            &model[0],
+            Widget::Input { content, .. } if content == "日é本"
+        ));
+        assert_eq!(backend.row(0), "  > 日é本");
+        // label and margin, then a wide and a narrow character
+        assert_eq!(backend.cursor(), (0, 7));
+    }
+
+    #[test]
+    fn input_graphemes() {
+        let mut model = model("INPUT()\nTEXT(\"x\")");
+        // "e" followed by a combining acute accent is a single grapheme
+        let mut script = keys("ae\u{301}");
+        script.extend([Input::KeyLeft, Input::KeyBackspace, Input::KeyRight]);
+        script.extend(keys("👍"));
+        let mut backend = Headless::new(10, 20, script);
+
+        init(&mut backend, &mut model, &Options::default());
+
+        assert!(matches!(
+            &model[0],
+            Widget::Input { content, .. } if content == "e\u{301}👍"
+        ));
+        assert_eq!(backend.row(0), "  e\u{301}👍");
+        assert_eq!(backend.cursor(), (0, 5));
+    }
+
+    #[test]
+    fn input_backspace_grapheme() {
+        let mut model = model("INPUT(content=\"e\u{301}\")\nTEXT(\"x\")");
+        let mut backend = Headless::new(10, 20, vec![Input::KeyBackspace]);
+
+        init(&mut backend, &mut model, &Options::default());
+
+        // the accent goes with the letter it is on
+        assert!(matches!(
+            &model[0],
+            Widget::Input { content, .. } if content.is_empty()
+        ));
+        assert_eq!(backend.cursor(), (0, 2));
+    }
+
+    #[test]
+    fn highlight_wide_characters() {
+        let mut model = model(
+            "INPUT(filter=\"fuzzy\")\nTEXT(\"日本語 file\")\nTEXT(\"other\")",
+        );
+        let mut backend = Headless::new(10, 20, keys("本f"));
+
+        init(&mut backend, &mut model, &Options::default());
+
+        assert_eq!(backend.snapshot(), "  本f\n  日本語 file");
+        assert_eq!(backend.styled(1, Theme::default().highlight), "本f");
+        assert_eq!(backend.cursor(), (0, 5));
+    }
+
+    #[test]
+    fn input_editing_keys() {
+        let mut model = model("INPUT(content=\"foo bar\")\nTEXT(\"x\")");
+        let script = vec![
+            // alt-b, (escape then b), to the start of "bar" and delete forward
+            Input::Character('\u{1b}'),
+            Input::Character('b'),
+            Input::KeyDC,
+            // ctrl-a, ctrl-k, then ctrl-y the killed text back
+            Input::Character('\u{01}'),
+            Input::Character('\u{0b}'),
+            Input::Character('\u{19}'),
+            Input::KeyHome,
+        ];
+        let mut backend = Headless::new(10, 20, script);
+
+        init(&mut backend, &mut model, &Options::default());
+
+        assert!(matches!(
+            &model[0],
+            Widget::Input { content, .. } if content == "foo ar"
+        ));
+        assert_eq!(backend.row(0), "  foo ar");
+        assert_eq!(backend.cursor(), (0, 2));
+    }
+
+    #[test]
+    fn vim_keymap() {
+        let stdout =
+            "INPUT(filter=\"exact\")\nTEXT(\"a\")\nTEXT(\"b\")\nTEXT(\"c\")";
+        let vim = Keymap::preset("vim").unwrap();
+
+        // j/k and G move through the list, enter selects
+        let mut script = vec![Input::KeyDown];
+        script.extend(keys("jGk\n"));
+        let mut backend = Headless::new(10, 20, script);
+        let mut chosen = model(stdout);
+        let (break_condition, id) = init(
+            &mut backend,
+            &mut chosen,
+            &Options {
+                keymap: vim.clone(),
+                ..Options::default()
+            },
+        );
+        assert_eq!(break_condition, BreakCondition::SELECTION);
+        assert!(
+            matches!(find_widget_by_id(&chosen, id).unwrap(), Widget::Text { value, .. } if value == "b")
+        );
+
+        // gg jumps back to the filter, where keys are typed
+        let mut script = vec![Input::KeyDown];
+        script.extend(keys("Ggggj"));
+        let mut backend = Headless::new(10, 20, script);
+        init(
+            &mut backend,
+            &mut model(stdout),
+            &Options {
+                keymap: vim.clone(),
+                ..Options::default()
+            },
+        );
+        assert_eq!(backend.snapshot(), "  gj");
+
+        // / focuses the filter from the list
+        let mut script = vec![Input::KeyDown];
+        script.extend(keys("G/c"));
+        let mut backend = Headless::new(10, 20, script);
+        init(
+            &mut backend,
+            &mut model(stdout),
+            &Options {
+                keymap: vim,
+                ..Options::default()
+            },
+        );
+        assert_eq!(backend.snapshot(), "  c\n  c");
+        assert!(backend.cursor_visible());
+    }
+
+    #[test]
+    fn emacs_keymap() {
+        let mut model =
+            model("INPUT(content=\"ab\")\nTEXT(\"a\")\nTEXT(\"b\")");
+        let script = vec![
+            Input::Character('\u{0e}'),
+            Input::Character('\u{0e}'),
+            Input::Character('\u{10}'),
+            Input::Character('\n'),
+        ];
+        let mut backend = Headless::new(10, 20, script);
+
+        let (break_condition, id) = init(
+            &mut backend,
+            &mut model,
+            &Options {
+                keymap: Keymap::preset("emacs").unwrap(),
+                ..Options::default()
+            },
+        );
+
+        assert_eq!(break_condition, BreakCondition::SELECTION);
+        assert!(
+            matches!(find_widget_by_id(&model, id).unwrap(), Widget::Text { value, .. } if value == "a")
+        );
+    }
+
+    #[test]
+    fn custom_binding() {
+        let mut keymap = Keymap::default();
+        keymap.bind_str("ctrl-n = down").unwrap();
+        keymap.bind_str("x x = quit").unwrap();
+        let mut model = model("TEXT(\"a\")\nTEXT(\"b\")");
+        let mut script = vec![Input::Character('\u{0e}')];
+        script.extend(keys("xyxx\n"));
+        let mut backend = Headless::new(10, 20, script);
+
+        // "x y" is no binding, "x x" quits before enter is read
+        let (break_condition, _) = init(
+            &mut backend,
+            &mut model,
+            &Options {
+                keymap,
+                ..Options::default()
+            },
+        );
+
+        assert_eq!(break_condition, BreakCondition::QUIT);
+        assert_eq!(backend.snapshot(), "  a\n> b");
+    }
+
+    #[test]
+    fn checkbox_and_select() {
+        let mut model = model(
+            "CHECKBOX(label=\"a\")\nSELECT(label=\"b\" options=\"x|yy|z\")",
+        );
+        let script = vec![
+            Input::Character(' '),
+            Input::KeyDown,
+            Input::KeyRight,
+            Input::KeyRight,
+            Input::KeyLeft,
+        ];
+        let mut backend = Headless::new(10, 20, script);
+
+        // script runs out, (closing the terminal), which quits
+        let (break_condition, _) =
+            init(&mut backend, &mut model, &Options::default());
+
+        assert_eq!(break_condition, BreakCondition::QUIT);
+        assert_eq!(backend.snapshot(), "  [x] a\n> b< yy >");
+    }
+
+    #[test]
+    fn multi_select_marks() {
+        let mut model = model("TEXT(\"a\")\nTEXT(\"b\")");
+        let script = vec![Input::Character(' '), Input::KeyDown];
+        let mut backend = Headless::new(10, 20, script);
+
+        init(
+            &mut backend,
+            &mut model,
+            &Options {
+                multi_select: true,
+                ..Options::default()
+            },
+        );
+
+        assert_eq!(backend.snapshot(), " *a\n> b");
+        assert!(!backend.cursor_visible());
+    }
+
+    #[test]
+    fn scroll_down() {
+        let mut model = model("TEXT(\"a\")\nTEXT(\"b\")\nTEXT(\"c\")");
+        let script =
+            vec![Input::KeyDown, Input::KeyDown, Input::Character('\n')];
+        let mut backend = Headless::new(2, 20, script);
+
+        let (break_condition, id) =
+            init(&mut backend, &mut model, &Options::default());
+
+        assert_eq!(break_condition, BreakCondition::SELECTION);
+        assert!(
+            matches!(find_widget_by_id(&model, id).unwrap(), Widget::Text { value, .. } if value == "c")
+        );
+        assert_eq!(backend.snapshot(), "  b\n> c");
+    }
+
+    #[test]
+    fn page_and_jump() {
+        let stdout = (0..10)
+            .map(|i| format!("TEXT(\"row {}\")", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let script = vec![Input::KeyNPage, Input::KeyNPage, Input::KeyPPage];
+        let mut backend = Headless::new(3, 20, script);
+        init(&mut backend, &mut model(&stdout), &Options::default());
+        assert_eq!(backend.snapshot(), "> row 3\n  row 4\n  row 5");
+
+        let script = vec![Input::KeyEnd];
+        let mut backend = Headless::new(3, 20, script);
+        init(&mut backend, &mut model(&stdout), &Options::default());
+        assert_eq!(backend.snapshot(), "  row 7\n  row 8\n> row 9");
+
+        let script = vec![Input::KeyEnd, Input::KeyUp, Input::KeyHome];
+        let mut backend = Headless::new(3, 20, script);
+        init(&mut backend, &mut model(&stdout), &Options::default());
+        assert_eq!(backend.snapshot(), "> row 0\n  row 1\n  row 2");
+    }
+
+    #[test]
+    fn skip_hidden_rows() {
+        let stdout =
+            "INPUT(filter=\"exact\")\nTEXT(\"a1\")\nTEXT(\"b\")\nTEXT(\"a2\")";
+        let mut script = keys("a");
+        script.extend([Input::KeyDown, Input::KeyDown, Input::KeyDown]);
+        script.push(Input::Character('\n'));
+        let mut chosen = model(stdout);
+        let mut backend = Headless::new(10, 20, script);
+
+        let (_, id) = init(&mut backend, &mut chosen, &Options::default());
+
+        // the cursor stops on the last shown row, (not the blank rows below it)
+        assert!(
+            matches!(find_widget_by_id(&chosen, id).unwrap(), Widget::Text { value, .. } if value == "a2")
+        );
+        assert_eq!(backend.snapshot(), "  a\n  a1\n> a2");
+    }
+
+    #[test]
+    fn filter_above_input() {
+        let stdout = "TEXT(\"apple\")\nTEXT(\"banana\")\n\
+                      INPUT(filter=\"exact\")\nTEXT(\"cherry\")";
+        let mut script = vec![Input::KeyDown, Input::KeyDown];
+        script.extend(keys("ch"));
+        script.push(Input::Character('\n'));
+        let mut chosen = model(stdout);
+        let mut backend = Headless::new(10, 20, script);
+
+        let (break_condition, id) =
+            init(&mut backend, &mut chosen, &Options::default());
+
+        // rows hidden and reordered above the input move the cursor with it
+        assert_eq!(break_condition, BreakCondition::INPUT);
+        assert!(
+            matches!(find_widget_by_id(&chosen, id).unwrap(), Widget::Input { content, .. } if content == "ch")
+        );
+        assert_eq!(backend.snapshot(), "  cherry\n  ch");
+        assert_eq!(backend.cursor(), (1, 4));
+    }
+
+    #[test]
+    fn filter_while_scrolled() {
+        let stdout = (0..10)
+            .map(|i| format!("TEXT(\"row {}\")", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut script = vec![Input::KeyEnd, Input::KeyHome];
+        script.extend(keys("9"));
+        let mut backend = Headless::new(3, 20, script);
+
+        init(
+            &mut backend,
+            &mut model(&format!("INPUT(filter=\"exact\")\n{}", stdout)),
+            &Options::default(),
+        );
+
+        // scrolled to the bottom and back to the input, the shorter list is shown from its top
+        assert_eq!(backend.snapshot(), "  9\n  row 9");
+    }
+
+    #[test]
+    fn resize() {
+        let stdout = (0..10)
+            .map(|i| format!("TEXT(\"row {}\")", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        // shrinking keeps the cursor's row in view
+        let mut script = vec![Input::KeyDown; 4];
+        script.push(Input::KeyResize);
+        let mut backend = Headless::new(6, 20, script);
+        backend.queue_resize(2, 20);
+        init(&mut backend, &mut model(&stdout), &Options::default());
+        assert_eq!(backend.snapshot(), "  row 3\n> row 4");
+
+        // growing shows the rows above it again, (no rows left empty below the model)
+        let mut script = vec![Input::KeyEnd, Input::KeyResize, Input::KeyUp];
+        let mut backend = Headless::new(2, 20, script.clone());
+        backend.queue_resize(4, 20);
+        init(&mut backend, &mut model(&stdout), &Options::default());
+        assert_eq!(backend.snapshot(), "  row 6\n  row 7\n> row 8\n  row 9");
+
+        // page keys move by the new height
+        script.push(Input::KeyPPage);
+        let mut backend = Headless::new(2, 20, script);
+        backend.queue_resize(4, 20);
+        init(&mut backend, &mut model(&stdout), &Options::default());
+        assert_eq!(backend.snapshot(), "> row 4\n  row 5\n  row 6\n  row 7");
+    }
+
+    #[test]
+    fn mouse_click() {
+        let stdout = "INPUT(label=\"> \")\nTEXT(\"a\")\nTEXT(\"b\")";
+
+        // a click on text chooses it
+        let mut chosen = model(stdout);
+        let mut backend = Headless::new(10, 20, vec![Input::KeyMouse]);
+        backend.queue_mouse(Mouse::Click { y: 2, x: 3 });
+        let (break_condition, id) =
+            init(&mut backend, &mut chosen, &Options::default());
+        assert_eq!(break_condition, BreakCondition::SELECTION);
+        assert!(
+            matches!(find_widget_by_id(&chosen, id).unwrap(), Widget::Text { value, .. } if value == "b")
+        );
+
+        // a click on an input moves its cursor to the column clicked, (past its label)
+        let mut script = vec![Input::KeyDown];
+        script.extend([Input::KeyMouse, Input::KeyMouse]);
+        script.extend(keys("x"));
+        let mut typed =
+            model(&stdout.replace("INPUT(", "INPUT(content=\"日本\","));
+        let mut backend = Headless::new(10, 20, script);
+        backend.queue_mouse(Mouse::Click { y: 0, x: 6 });
+        backend.queue_mouse(Mouse::Click { y: 5, x: 0 });
+        init(&mut backend, &mut typed, &Options::default());
+        assert!(
+            matches!(&typed[0], Widget::Input { content, .. } if content == "日x本")
+        );
+    }
+
+    #[test]
+    fn theme_styles() {
+        let mut theme = Theme::default();
+        theme
+            .set_lines(
+                "selected = bg=blue\nlabel = fg=green\nborder = bold=true",
+            )
+            .unwrap();
+        let stdout = "INPUT(label=\"q \" filter=\"exact\")\nTEXT(\"ab\" fg=\"red\")\nTEXT(\"b\")";
+        let mut script = keys("b");
+        script.push(Input::KeyDown);
+        let mut backend = Headless::new(10, 20, script);
+
+        init(
+            &mut backend,
+            &mut model(stdout),
+            &Options {
+                theme,
+                ..Options::default()
+            },
+        );
+
+        let label = Style {
+            fg: Some(Color::Green),
+            ..Style::default()
+        };
+        assert_eq!(backend.styled(0, label), "q ");
+        // the selected row, (its own colour drawn over the selection's), with its match highlighted
+        let selected = Style {
+            fg: Some(Color::Red),
+            bg: Some(Color::Blue),
+            ..Style::default()
+        };
+        assert_eq!(backend.styled(1, selected), "a");
+        assert_eq!(backend.styled(1, theme.highlight.over(selected)), "b");
+        assert_eq!(backend.styled(1, theme.border), ">");
+        assert_eq!(backend.styled(2, theme.highlight), "b");
+    }
+
+    #[test]
+    fn input_placeholder() {
+        let stdout = "INPUT(label=\"> \" placeholder=\"search\")";
+        let theme = Theme::default();
+
+        let mut backend = Headless::new(10, 20, vec![]);
+        init(
+            &mut backend,
+            &mut model(stdout),
+            &Options {
+                theme,
+                ..Options::default()
+            },
+        );
+        assert_eq!(backend.snapshot(), "  > search");
+        assert_eq!(backend.styled(0, theme.placeholder), "search");
+        assert_eq!(backend.cursor(), (0, 4));
+
+        let mut script = keys("a");
+        script.push(Input::KeyBackspace);
+        script.extend(keys("b"));
+        let mut backend = Headless::new(10, 20, script);
+        init(
+            &mut backend,
+            &mut model(stdout),
+            &Options {
+                theme,
+                ..Options::default()
+            },
+        );
+        assert_eq!(backend.snapshot(), "  > b");
+    }
+
+    #[test]
+    fn input_max_width() {
+        let mut typed = model("INPUT(max_width=3)");
+        let mut backend = Headless::new(10, 20, keys("abcd"));
+        init(&mut backend, &mut typed, &Options::default());
+        assert!(
+            matches!(&typed[0], Widget::Input { content, .. } if content == "abc")
+        );
+
+        // a wide character that does not fit is dropped whole
+        let mut typed = model("INPUT(max_width=3)");
+        let mut backend = Headless::new(10, 20, keys("日本"));
+        init(&mut backend, &mut typed, &Options::default());
+        assert!(
+            matches!(&typed[0], Widget::Input { content, .. } if content == "日")
+        );
+    }
+
+    #[test]
+    fn input_scrolls() {
+        let stdout = "INPUT(label=\"> \")\nTEXT(\"a\")";
+
+        // a field of 5 columns, (10 less the margin, label and a column for the cursor)
+        let mut backend = Headless::new(10, 10, keys("abcdefgh"));
+        init(&mut backend, &mut model(stdout), &Options::default());
+        assert_eq!(backend.snapshot(), "  > defgh\n  a");
+        assert_eq!(backend.cursor(), (0, 9));
+
+        let mut script = keys("abcdefgh");
+        script.push(Input::KeyHome);
+        let mut backend = Headless::new(10, 10, script);
+        init(&mut backend, &mut model(stdout), &Options::default());
+        assert_eq!(backend.snapshot(), "  > abcde\n  a");
+        assert_eq!(backend.cursor(), (0, 4));
+
+        // other rows show the start of the content
+        let mut script = keys("abcdefgh");
+        script.push(Input::KeyDown);
+        let mut backend = Headless::new(10, 10, script);
+        init(&mut backend, &mut model(stdout), &Options::default());
+        assert_eq!(backend.snapshot(), "  > abcde\n> a");
+    }
+
+    #[test]
+    fn mouse_wheel() {
+        let stdout = (0..10)
+            .map(|i| format!("TEXT(\"row {}\")", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut backend = Headless::new(3, 20, vec![Input::KeyMouse; 4]);
+        for mouse in [
+            Mouse::ScrollDown,
+            Mouse::ScrollDown,
+            Mouse::ScrollDown,
+            Mouse::ScrollUp,
+        ] {
+            backend.queue_mouse(mouse);
+        }
+
+        init(&mut backend, &mut model(&stdout), &Options::default());
+
+        assert_eq!(backend.snapshot(), "  row 1\n> row 2\n  row 3");
+    }
+}
+
+#[cfg(test)]
+mod editor_test {
+    use stacklet::interface::editor::{EditAction, LineEditor};
+
+    fn editor(content: &str, actions: &[EditAction]) -> LineEditor {
+        let mut editor = LineEditor::new(content);
+        for action in actions {
+            editor.apply(*action);
+        }
+        editor
+    }
+
+    #[test]
+    fn motion() {
+        use EditAction::*;
+
+        assert_eq!(editor("abc", &[]).cursor(), 3);
+        assert_eq!(editor("abc", &[Home, Right]).cursor(), 1);
+        assert_eq!(editor("abc", &[Left, Left, Left, Left]).cursor(), 0);
+        assert_eq!(editor("abc", &[Home, End, Right]).cursor(), 3);
+        assert_eq!(editor("foo bar-baz", &[WordLeft]).cursor(), 8);
+        assert_eq!(editor("foo bar-baz", &[WordLeft, WordLeft]).cursor(), 4);
+        assert_eq!(editor("foo bar-baz", &[Home, WordRight]).cursor(), 3);
+        assert_eq!(
+            editor("foo bar-baz", &[Home, WordRight, WordRight])
+                .before_cursor(),
+            "foo bar"
+        );
+    }
+
+    #[test]
+    fn delete() {
+        use EditAction::*;
+
+        let mut editor = LineEditor::new("abc");
+        assert!(!editor.apply(DeleteForward));
+        assert!(editor.apply(DeleteBackward));
+        assert_eq!(editor.content(), "ab");
+        editor.apply(Home);
+        assert!(!editor.apply(DeleteBackward));
+        assert!(editor.apply(DeleteForward));
+        assert_eq!(editor.content(), "b");
+        assert_eq!(editor.cursor(), 0);
+    }
+
+    #[test]
+    fn kill_and_yank() {
+        use EditAction::*;
+
+        let killed = editor("cat foo.txt  ", &[DeleteWordBackward]);
+        assert_eq!(killed.content(), "cat ");
+
+        let yanked = editor("cat foo.txt", &[DeleteWordBackward, Home, Yank]);
+        assert_eq!(yanked.content(), "foo.txtcat ");
+        assert_eq!(yanked.cursor(), 7);
+
+        let killed = editor("hello world", &[WordLeft, KillToEnd]);
+        assert_eq!(killed.content(), "hello ");
+        let killed = editor("hello world", &[WordLeft, KillToStart, End, Yank]);
+        assert_eq!(killed.content(), "worldhello ");
+
+        // killing nothing keeps what was killed before
+        let kept = editor("ab", &[Left, KillToEnd, End, KillToEnd, Yank]);
+        assert_eq!(kept.content(), "ab");
+    }
+
+    #[test]
+    fn multi_byte() {
+        use EditAction::*;
+
+        let edited = editor("日本語 ok", &[WordLeft, Left, DeleteWordBackward]);
+        assert_eq!(edited.content(), " ok");
+        let edited = editor("e\u{301}日", &[Home, DeleteForward, Insert('x')]);
+        assert_eq!(edited.content(), "x日");
+        assert_eq!(edited.before_cursor(), "x");
+    }
+}
+
+#[cfg(test)]
+mod keymap_test {
+    use pancurses::Input;
+    use stacklet::interface::editor::EditAction;
+    use stacklet::interface::keymap::{Action, Key, Keymap, Lookup};
+
+    fn key(s: &str) -> Key {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_key() {
+        assert_eq!(key("j"), Key::new(Input::Character('j'), false));
+        assert_eq!(key("G"), Key::new(Input::Character('G'), false));
+        assert_eq!(key("ctrl-n"), Key::new(Input::Character('\u{0e}'), false));
+        assert_eq!(key("alt-b"), Key::new(Input::Character('b'), true));
+        assert_eq!(key("enter"), Key::new(Input::KeyEnter, false));
+        assert_eq!(
+            key("backspace"),
+            Key::new(Input::Character('\u{7f}'), false)
+        );
+        assert_eq!(key("pgdn"), Key::new(Input::KeyNPage, false));
+        assert!("ctrl-".parse::<Key>().is_err());
+        assert!("ctrl-ab".parse::<Key>().is_err());
+        assert!("hyper-x".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn parse_action() {
+        assert_eq!("down".parse(), Ok(Action::Down));
+        assert_eq!("focus-filter".parse(), Ok(Action::FocusFilter));
+        assert_eq!(
+            "kill-line".parse(),
+            Ok(Action::Edit(EditAction::KillToEnd))
+        );
+        assert!("fly".parse::<Action>().is_err());
+    }
+
+    #[test]
+    fn lookup_sequences() {
+        let vim = Keymap::preset("vim").unwrap();
+
+        assert_eq!(vim.lookup(&[key("j")]), Lookup::Action(Action::Down));
+        assert_eq!(vim.lookup(&[key("g")]), Lookup::Prefix);
+        assert_eq!(
+            vim.lookup(&[key("g"), key("g")]),
+            Lookup::Action(Action::Top)
+        );
+        assert_eq!(vim.lookup(&[key("g"), key("x")]), Lookup::Unbound);
+        assert_eq!(Keymap::default().lookup(&[key("j")]), Lookup::Unbound);
+        assert!(Keymap::preset("nano").is_none());
+    }
+
+    #[test]
+    fn bind_lines() {
+        let mut keymap = Keymap::default();
+        keymap
+            .bind_lines("# navigation\n\nctrl-n = down\n  z z = bottom  \n")
+            .unwrap();
+
+        assert_eq!(
+            keymap.lookup(&[key("ctrl-n")]),
+            Lookup::Action(Action::Down)
+        );
+        assert_eq!(
+            keymap.lookup(&[key("z"), key("z")]),
+            Lookup::Action(Action::Bottom)
+        );
+        assert_eq!(
+            keymap.bind_lines("up = up\nctrl-n down"),
+            Err("line 2: expected keys = action in 'ctrl-n down'".to_string())
+        );
+        assert_eq!(
+            keymap.bind_str("hyper-x = up"),
+            Err("unknown key 'hyper-x'".to_string())
+        );
+        assert_eq!(
+            keymap.bind_str("x = fly"),
+            Err("unknown action 'fly'".to_string())
+        );
+        assert!(keymap.load("/nonexistent/keymap").is_err());
+    }
+}
+
+#[cfg(test)]
+mod theme_test {
+    use stacklet::interface::theme::{Color, Style, Theme};
+
+    #[test]
+    fn parse_style() {
+        let mut style = Style::default();
+        style.set("fg", "Cyan").unwrap();
+        style.set("bg", "236").unwrap();
+        style.set("underline", "true").unwrap();
+        assert_eq!(style.fg, Some(Color::Cyan));
+        assert_eq!(style.bg, Some(Color::Indexed(236)));
+        assert_eq!(style.underline, Some(true));
+        assert!(style.set("fg", "mauve").is_err());
+        assert!(style.set("bold", "1").is_err());
+        assert!(style.set("blink", "true").is_err());
+    }
+
+    #[test]
+    fn layered_styles() {
+        let base = Style {
+            fg: Some(Color::White),
+            bg: Some(Color::Black),
+            ..Style::default()
+        };
+        let over = Style {
+            fg: Some(Color::Default),
+            bold: Some(true),
+            ..Style::default()
+        };
+        assert_eq!(
+            over.over(base),
+            Style {
+                fg: Some(Color::Default),
+                bg: Some(Color::Black),
+                bold: Some(true),
+                ..Style::default()
+            }
+        );
+    }
+
+    #[test]
+    fn set_lines() {
+        let mut theme = Theme::default();
+        theme
+            .set_lines(
+                "# dark\n\nhighlight = fg=yellow\n  normal = fg=7 bg=default  ",
+            )
+            .unwrap();
+        assert_eq!(theme.highlight.fg, Some(Color::Yellow));
+        // attributes not set keep their default
+        assert_eq!(theme.highlight.bold, Some(true));
+        assert_eq!(theme.normal.bg, Some(Color::Default));
+
+        assert_eq!(
+            theme.set_lines("normal = fg=red\nselected"),
+            Err("line 2: expected part = style in 'selected'".to_string())
+        );
+        assert_eq!(
+            theme.set_str("frame = bold=true"),
+            Err("unknown part 'frame'".to_string())
+        );
+        assert_eq!(
+            theme.set_str("normal = fg"),
+            Err("expected attribute=value in 'fg'".to_string())
+        );
+        assert_eq!(
+            theme.set_str("normal = fg=mauve"),
+            Err("invalid attribute 'fg=mauve'".to_string())
+        );
+        assert!(theme.load("/nonexistent/theme").is_err());
+    }
+}
+
+#[cfg(test)]
+mod backend_test {
+    use pancurses::Input;
+    use stacklet::interface::backend::{decode_key, Height, Mouse};
+
+    #[test]
+    fn parse_height() {
+        assert_eq!("10".parse(), Ok(Height::Rows(10)));
+        assert_eq!("40%".parse(), Ok(Height::Percent(40)));
+        assert!("140%".parse::<Height>().is_err());
+        assert!("ten".parse::<Height>().is_err());
+        assert!("".parse::<Height>().is_err());
+
+        assert_eq!(Height::Rows(10).rows(24), 10);
+        assert_eq!(Height::Rows(40).rows(24), 24);
+        assert_eq!(Height::Rows(0).rows(24), 1);
+        assert_eq!(Height::Percent(50).rows(24), 12);
+        assert_eq!(Height::Percent(1).rows(24), 1);
+    }
+
+    #[test]
+    fn decode_keys() {
+        assert_eq!(decode_key(b""), (None, None, 0));
+        assert_eq!(decode_key(b"ab"), (Some(Input::Character('a')), None, 1));
+        assert_eq!(
+            decode_key("é!".as_bytes()),
+            (Some(Input::Character('é')), None, 2)
+        );
+        assert_eq!(decode_key(b"\x1b[A"), (Some(Input::KeyUp), None, 3));
+        assert_eq!(decode_key(b"\x1bOB"), (Some(Input::KeyDown), None, 3));
+        assert_eq!(decode_key(b"\x1b[5~"), (Some(Input::KeyPPage), None, 4));
+        assert_eq!(decode_key(b"\x1b[3~"), (Some(Input::KeyDC), None, 4));
+        assert_eq!(decode_key(b"\x1b[Z"), (Some(Input::KeySTab), None, 3));
+        assert_eq!(decode_key(b"\x03"), (Some(Input::KeyCancel), None, 1));
+        // unknown sequences are skipped whole
+        assert_eq!(decode_key(b"\x1b[99Xa"), (None, None, 5));
+    }
+
+    #[test]
+    fn decode_split_character() {
+        // the start of a character waits for the rest, taking up no bytes
+        let bytes = "€".as_bytes();
+        assert_eq!(decode_key(&bytes[..1]), (None, None, 0));
+        assert_eq!(decode_key(&bytes[..2]), (None, None, 0));
+        assert_eq!(decode_key(bytes), (Some(Input::Character('€')), None, 3));
+        // an invalid one is skipped a byte at a time
+        assert_eq!(decode_key(b"\xe2\x82a"), (None, None, 1));
+        assert_eq!(decode_key(b"\x82a"), (None, None, 1));
+    }
+
+    #[test]
+    fn decode_escape() {
+        // alone, or followed by the key held with alt
+        assert_eq!(
+            decode_key(b"\x1b"),
+            (Some(Input::Character('\u{1b}')), None, 1)
+        );
+        assert_eq!(
+            decode_key(b"\x1bv"),
+            (Some(Input::Character('\u{1b}')), None, 1)
+        );
+    }
+
+    #[test]
+    fn decode_mouse() {
+        assert_eq!(
+            decode_key(b"\x1b[<0;5;3M"),
+            (Some(Input::KeyMouse), Some(Mouse::Click { y: 2, x: 4 }), 9)
+        );
+        assert_eq!(
+            decode_key(b"\x1b[<64;5;3M"),
+            (Some(Input::KeyMouse), Some(Mouse::ScrollUp), 10)
+        );
+        assert_eq!(
+            decode_key(b"\x1b[<65;5;3M"),
+            (Some(Input::KeyMouse), Some(Mouse::ScrollDown), 10)
+        );
+        // releases are ignored
+        assert_eq!(decode_key(b"\x1b[<0;5;3m"), (None, None, 9));
+    }
+}
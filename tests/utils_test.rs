@@ -1,21 +1,210 @@
 #[cfg(test)]
 mod filter_test {
-    use stacklet::utils::filter::{exact_match, fuzzy_match};
+    use regex::Regex;
+    use stacklet::utils::filter::{
+        exact_match, fold, fuzzy_match, fuzzy_score, prefix_match, regex_match,
+        Candidate, Glob, Match, Query, Term, TermKind,
+    };
 
     #[test]
     fn exact_match_positive() {
-        assert!(exact_match("hello, world!", "o"));
-        assert!(exact_match("foo", "foo"));
-        assert!(exact_match("<>!;;", "!;;"));
-        assert!(exact_match("heart", "ear"));
+        assert!(exact_match("hello, world!", "o").is_some());
+        assert!(exact_match("foo", "foo").is_some());
+        assert!(exact_match("<>!;;", "!;;").is_some());
+        assert!(exact_match("heart", "ear").is_some());
     }
 
     #[test]
     fn exact_match_negative() {
-        assert!(!exact_match("hello, world!", "z"));
-        assert!(!exact_match("foo", "bar"));
-        assert!(!exact_match("<>!;;", "^"));
-        assert!(!exact_match("rust", "rst"));
+        assert!(exact_match("hello, world!", "z").is_none());
+        assert!(exact_match("foo", "bar").is_none());
+        assert!(exact_match("<>!;;", "^").is_none());
+        assert!(exact_match("rust", "rst").is_none());
+    }
+
+    #[test]
+    fn exact_match_positions() {
+        assert_eq!(
+            exact_match("heart", "ear").unwrap().positions,
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            exact_match("été ear", "ear").unwrap().positions,
+            vec![4, 5, 6]
+        );
+        assert!(exact_match("foo", "").unwrap().positions.is_empty());
+    }
+
+    #[test]
+    fn prefix_match_positions() {
+        assert_eq!(prefix_match("heart", "he").unwrap().positions, vec![0, 1]);
+        assert!(prefix_match("heart", "ear").is_none());
+    }
+
+    #[test]
+    fn regex_match_positions() {
+        let regex = Regex::new(r"\d+").unwrap();
+        assert_eq!(
+            regex_match("é 404 error", &regex).unwrap().positions,
+            vec![2, 3, 4]
+        );
+        assert!(regex_match("no digits", &regex).is_none());
+    }
+
+    #[test]
+    fn glob_match() {
+        let glob = |pattern| Glob::new(pattern).unwrap();
+
+        assert!(glob("*.rs").find("main.rs").is_some());
+        assert!(glob("*.rs").find("main.rs.bak").is_none());
+        assert!(glob("m??n*").find("main.rs").is_some());
+        assert!(glob("[a-m]ain*").find("main.rs").is_some());
+        assert!(glob("[!a-m]ain*").find("main.rs").is_none());
+        assert!(glob("[]]").find("]").is_some());
+        assert!(glob("a*b*c").find("aXbXbXc").is_some());
+        assert!(glob("a*b*c").find("aXbXbX").is_none());
+        assert!(glob("*").find("").is_some());
+        assert_eq!(glob("*.rs").find("a.rs").unwrap().positions, vec![1, 2, 3]);
+        assert_eq!(
+            Glob::new("[a-").unwrap_err(),
+            "unterminated character class"
+        );
+    }
+
+    #[test]
+    fn query_parse() {
+        let term = |kind, text: &str, negated| Term {
+            kind,
+            text: text.to_string(),
+            negated,
+        };
+
+        assert_eq!(
+            Query::parse("  ^core go$ | rb$ | 'py !^test  ", TermKind::Fuzzy)
+                .groups,
+            vec![
+                vec![term(TermKind::Prefix, "core", false)],
+                vec![
+                    term(TermKind::Suffix, "go", false),
+                    term(TermKind::Suffix, "rb", false),
+                    term(TermKind::Exact, "py", false),
+                ],
+                vec![term(TermKind::Prefix, "test", true)],
+            ]
+        );
+        assert_eq!(
+            Query::parse("^a$ !b 'c a\\ b", TermKind::Exact).groups,
+            vec![
+                vec![term(TermKind::Equal, "a", false)],
+                vec![term(TermKind::Exact, "b", true)],
+                vec![term(TermKind::Fuzzy, "c", false)],
+                vec![term(TermKind::Exact, "a b", false)],
+            ]
+        );
+        // operators alone and dangling alternatives are ignored
+        assert!(Query::parse("! ^ $ ' |", TermKind::Fuzzy).groups.is_empty());
+    }
+
+    #[test]
+    fn query_find() {
+        let find = |query, s| Query::parse(query, TermKind::Fuzzy).find(s);
+
+        assert!(find("", "anything").is_some());
+        assert!(find("src rs$", "src/main.rs").is_some());
+        assert!(find("src rs$", "src/main.go").is_none());
+        assert!(find("!test", "src/main.rs").is_some());
+        assert!(find("!test", "tests/main.rs").is_none());
+        assert!(find("go$ | rs$", "src/main.rs").is_some());
+        assert!(find("^main", "src/main.rs").is_none());
+        assert!(find("'mn", "src/main.rs").is_none());
+        assert!(find("^src", "SRC/main.rs").is_some());
+        assert_eq!(
+            find("^sr rs$ !x", "src/main.rs").unwrap().positions,
+            vec![0, 1, 9, 10]
+        );
+    }
+
+    #[test]
+    fn query_find_case_sensitive() {
+        let query = Query::parse("Main", TermKind::Fuzzy);
+        let candidate = Candidate::new("src/main.rs");
+
+        assert!(query.find_candidate(&candidate, true).is_none());
+        assert!(query
+            .find_candidate(&Candidate::new("src/Main.rs"), true)
+            .is_some());
+        assert!(Query::parse("main", TermKind::Exact)
+            .find_candidate(&candidate, false)
+            .is_some());
+    }
+
+    #[test]
+    fn fold_diacritics() {
+        assert_eq!(fold("Crème brûlée"), "Creme brulee");
+        assert_eq!(fold("Ærø"), "AEro");
+        assert_eq!(fold("ﬁle"), "file");
+        assert_eq!(fold("plain"), "plain");
+    }
+
+    #[test]
+    fn query_find_folded() {
+        let find = |query, s: &str| {
+            let candidate = Candidate::folded(s);
+            Query::parse(query, TermKind::Fuzzy)
+                .find_candidate(&candidate, false)
+                .map(|found| candidate.unfold(found).positions)
+        };
+
+        assert_eq!(find("cafe", "café"), Some(vec![0, 1, 2, 3]));
+        // both letters of "ae" come from "Æ"
+        assert_eq!(find("aero", "Ærø"), Some(vec![0, 1, 2]));
+        assert_eq!(find("ro", "Ærø"), Some(vec![1, 2]));
+        assert_eq!(find("cafe", "Zoë's café"), Some(vec![6, 7, 8, 9]));
+        // without folding "é" is not an "e", (only within the typo threshold)
+        assert_eq!(
+            Query::parse("cafe", TermKind::Fuzzy).find("café"),
+            Some(Match {
+                score: i64::MIN,
+                positions: Vec::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn query_find_typo() {
+        let find = |query, s| Query::parse(query, TermKind::Fuzzy).find(s);
+
+        // typos match below every subsequence match, highlighting nothing
+        assert_eq!(
+            find("pyhton", "python"),
+            Some(Match {
+                score: i64::MIN,
+                positions: Vec::new(),
+            })
+        );
+        assert!(find("pyhton", "rust").is_none());
+        assert!(find("pyt", "python").unwrap().score > i64::MIN);
+    }
+
+    #[test]
+    fn query_narrows() {
+        let narrows = |query, last| {
+            Query::parse(query, TermKind::Exact)
+                .narrows(&Query::parse(last, TermKind::Exact))
+        };
+
+        assert!(narrows("abc", "ab"));
+        assert!(narrows("ab c", "ab"));
+        assert!(narrows("^abc", "^ab"));
+        assert!(narrows("^abc$", "bc$"));
+        assert!(narrows("ab", ""));
+        // quoted terms are fuzzy where exact is the default
+        assert!(!narrows("'abc", "'ab"));
+        assert!(!narrows("!abc", "!ab"));
+        assert!(!narrows("abc | d", "ab | d"));
+        assert!(!narrows("ab$c", "ab$"));
+        assert!(!narrows("ab\\ c", "ab\\"));
+        assert!(!narrows("ab", "abc"));
     }
 
     #[test]
@@ -35,6 +224,43 @@ mod filter_test {
         assert!(!fuzzy_match("python", "nohtyp"));
         assert!(!fuzzy_match("big dogs dig deep holes", "abcdefghijklmnop"));
     }
+
+    #[test]
+    fn fuzzy_score_subsequence() {
+        assert!(fuzzy_score("firefox", "ff").is_some());
+        assert!(fuzzy_score("FireFox", "ff").is_some());
+        assert_eq!(fuzzy_score("anything", ""), Some(Match::default()));
+        assert!(fuzzy_score("firefox", "fx").is_some());
+        assert!(fuzzy_score("firefox", "xf").is_none());
+        assert!(fuzzy_score("fire", "fires").is_none());
+    }
+
+    #[test]
+    fn fuzzy_score_ranking() {
+        let score = |s| fuzzy_score(s, "ff").unwrap().score;
+
+        // word boundaries and camelCase beat matches inside a word
+        assert!(score("fire fox") > score("offoff"));
+        assert!(score("fireFox") > score("firefox"));
+        // consecutive runs beat gaps
+        assert!(score("staff") > score("stafof"));
+        assert!(
+            fuzzy_score("src/main.rs", "main").unwrap().score
+                > fuzzy_score("my_animation", "main").unwrap().score
+        );
+        // positions follow the best alignment, not the first one found
+        assert_eq!(
+            fuzzy_score("fire fox", "ff").unwrap().positions,
+            vec![0, 5]
+        );
+        assert_eq!(fuzzy_score("of_ff", "ff").unwrap().positions, vec![3, 4]);
+        assert_eq!(
+            fuzzy_score("src/main.rs", "mrs").unwrap().positions,
+            vec![4, 9, 10]
+        );
+        // shorter gaps beat longer ones
+        assert!(score("f_xf") > score("f_xxxxf"));
+    }
 }
 
 #[cfg(test)]
@@ -51,3 +277,52 @@ mod helpers_test {
         assert!(find_widget_by_y_mut(&mut Vec::new(), 0).is_none());
     }
 }
+
+#[cfg(test)]
+mod text_test {
+    use stacklet::utils::text::{
+        display_width, fit_width, grapheme_at_column, grapheme_count,
+        grapheme_index, grapheme_offset,
+    };
+
+    #[test]
+    fn display_width_wide() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("日本"), 4);
+        assert_eq!(display_width("e\u{301}"), 1);
+        assert_eq!(display_width("👍"), 2);
+    }
+
+    #[test]
+    fn grapheme_positions() {
+        let s = "ae\u{301}日";
+        assert_eq!(grapheme_count(s), 3);
+        assert_eq!(grapheme_offset(s, 0), 0);
+        assert_eq!(grapheme_offset(s, 1), 1);
+        assert_eq!(grapheme_offset(s, 2), 4);
+        assert_eq!(grapheme_offset(s, 3), s.len());
+        assert_eq!(grapheme_offset(s, 9), s.len());
+        assert_eq!(grapheme_index(s, 4), 2);
+    }
+
+    #[test]
+    fn grapheme_columns() {
+        let s = "ae\u{301}日b";
+        assert_eq!(grapheme_at_column(s, 0), 0);
+        assert_eq!(grapheme_at_column(s, 1), 1);
+        assert_eq!(grapheme_at_column(s, 2), 2);
+        assert_eq!(grapheme_at_column(s, 3), 2);
+        assert_eq!(grapheme_at_column(s, 4), 3);
+        assert_eq!(grapheme_at_column(s, 9), 4);
+    }
+
+    #[test]
+    fn fit_columns() {
+        let s = "ae\u{301}日b";
+        assert_eq!(fit_width(s, 0), "");
+        assert_eq!(fit_width(s, 2), "ae\u{301}");
+        assert_eq!(fit_width(s, 3), "ae\u{301}");
+        assert_eq!(fit_width(s, 4), "ae\u{301}日");
+        assert_eq!(fit_width(s, 9), s);
+    }
+}